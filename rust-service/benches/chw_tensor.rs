@@ -0,0 +1,24 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use image::{Rgb, RgbImage};
+use rust_service::image::{to_chw_tensor_chunked, to_chw_tensor_scalar};
+
+fn gradient() -> RgbImage {
+    RgbImage::from_fn(224, 224, |x, y| {
+        Rgb([x as u8, y as u8, (x as u8).wrapping_add(y as u8)])
+    })
+}
+
+fn chw_tensor(c: &mut Criterion) {
+    let image = gradient();
+    let mut group = c.benchmark_group("chw_tensor");
+    group.bench_function("scalar", |b| {
+        b.iter(|| to_chw_tensor_scalar(black_box(&image)))
+    });
+    group.bench_function("chunked", |b| {
+        b.iter(|| to_chw_tensor_chunked(black_box(&image)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, chw_tensor);
+criterion_main!(benches);
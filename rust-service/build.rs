@@ -1,8 +1,16 @@
+use std::{env, path::PathBuf};
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::configure().build_server(true).compile(
-        &["proto/verify.proto", "proto/triton/grpc_service.proto"],
-        &["proto", "proto/triton"],
-    )?;
+    // Compiled on its own so the reflection descriptor set only advertises
+    // services this binary actually serves.
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    tonic_build::configure()
+        .build_server(true)
+        .file_descriptor_set_path(out_dir.join("verify_descriptor.bin"))
+        .compile(&["proto/verify.proto"], &["proto"])?;
+    tonic_build::configure()
+        .build_server(true)
+        .compile(&["proto/triton/grpc_service.proto"], &["proto/triton"])?;
     println!("cargo:rerun-if-changed=proto/verify.proto");
     println!("cargo:rerun-if-changed=proto/triton/grpc_service.proto");
     println!("cargo:rerun-if-changed=proto/triton/health.proto");
@@ -0,0 +1,147 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::decision::Decision;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditRecord {
+    pub request_id: String,
+    pub user_id_hash: String,
+    pub model_fingerprint: String,
+    pub score: f32,
+    pub decision: Decision,
+    pub timestamp_ms: i64,
+    pub hmac: String,
+}
+
+impl AuditRecord {
+    pub fn new(
+        request_id: impl Into<String>,
+        user_id: &str,
+        model_fingerprint: impl Into<String>,
+        score: f32,
+        decision: Decision,
+        timestamp_ms: i64,
+    ) -> Self {
+        Self {
+            request_id: request_id.into(),
+            user_id_hash: hex(&Sha256::digest(user_id.as_bytes())),
+            model_fingerprint: model_fingerprint.into(),
+            score,
+            decision,
+            timestamp_ms,
+            hmac: String::new(),
+        }
+    }
+
+    // Newline-separated so no field can be shifted into its neighbour; the
+    // score uses its bit pattern to avoid float formatting differences.
+    fn signed_payload(&self) -> String {
+        format!(
+            "{}\n{}\n{}\n{:08x}\n{}\n{}",
+            self.request_id,
+            self.user_id_hash,
+            self.model_fingerprint,
+            self.score.to_bits(),
+            self.decision.as_str(),
+            self.timestamp_ms
+        )
+    }
+}
+
+pub struct AuditSigner {
+    secret: Vec<u8>,
+}
+
+impl AuditSigner {
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+        }
+    }
+
+    pub fn sign(&self, mut record: AuditRecord) -> AuditRecord {
+        record.hmac = hex(&self.mac(&record).finalize().into_bytes());
+        record
+    }
+
+    pub fn verify(&self, record: &AuditRecord) -> bool {
+        let Some(expected) = unhex(&record.hmac) else {
+            return false;
+        };
+        self.mac(record).verify_slice(&expected).is_ok()
+    }
+
+    fn mac(&self, record: &AuditRecord) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(record.signed_payload().as_bytes());
+        mac
+    }
+}
+
+pub trait AuditSink: Send + Sync {
+    fn persist(&self, record: &AuditRecord) -> io::Result<()>;
+}
+
+// Appends one JSON object per line; the file is never rewritten.
+pub struct JsonLinesAuditSink {
+    file: Mutex<File>,
+}
+
+impl JsonLinesAuditSink {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl AuditSink for JsonLinesAuditSink {
+    fn persist(&self, record: &AuditRecord) -> io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        let mut file = self.file.lock().expect("audit log lock poisoned");
+        file.write_all(&line)?;
+        file.flush()
+    }
+}
+
+impl From<AuditRecord> for crate::verify::AuditRecord {
+    fn from(record: AuditRecord) -> Self {
+        Self {
+            request_id: record.request_id,
+            user_id_hash: record.user_id_hash,
+            model_fingerprint: record.model_fingerprint,
+            score: record.score,
+            decision: crate::verify::VerificationDecision::from(record.decision) as i32,
+            timestamp_ms: record.timestamp_ms,
+            hmac: record.hmac,
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn unhex(value: &str) -> Option<Vec<u8>> {
+    if value.len() % 2 != 0 {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,41 @@
+use std::future::Future;
+
+use futures_util::future::join_all;
+use tokio::sync::Semaphore;
+use tonic::Status;
+
+use crate::verify::{
+    verify_image_result::Outcome, ItemError, VerifyImageResult, VerifyRequest, VerifyResponse,
+};
+
+// Runs `verify` over every image with at most `limit` in flight and returns the
+// results in request order. A failing image becomes an error entry rather than
+// failing the whole batch.
+pub async fn verify_each<F, Fut>(
+    images: Vec<VerifyRequest>,
+    limit: usize,
+    verify: F,
+) -> Vec<VerifyImageResult>
+where
+    F: Fn(VerifyRequest) -> Fut,
+    Fut: Future<Output = Result<VerifyResponse, Status>>,
+{
+    let semaphore = Semaphore::new(limit.max(1));
+    join_all(images.into_iter().map(|image| async {
+        let _permit = semaphore
+            .acquire()
+            .await
+            .expect("batch semaphore is never closed");
+        let outcome = match verify(image).await {
+            Ok(response) => Outcome::Response(response),
+            Err(status) => Outcome::Error(ItemError {
+                code: status.code() as i32,
+                message: status.message().to_string(),
+            }),
+        };
+        VerifyImageResult {
+            outcome: Some(outcome),
+        }
+    }))
+    .await
+}
@@ -0,0 +1,180 @@
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub const DEFAULT_SEGMENT: &str = "default";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Accept,
+    Review,
+    Reject,
+}
+
+impl Decision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Accept => "accept",
+            Self::Review => "review",
+            Self::Reject => "reject",
+        }
+    }
+}
+
+// Single accept/reject cut-off used when no decision table is configured.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VerifyThreshold(f32);
+
+impl VerifyThreshold {
+    pub fn new(value: f32) -> Result<Self, String> {
+        if !(0.0..=1.0).contains(&value) {
+            return Err(format!("threshold must be within [0, 1], got {value}"));
+        }
+        Ok(Self(value))
+    }
+
+    pub fn value(&self) -> f32 {
+        self.0
+    }
+
+    // Not finite means the model output is broken, which must not pass.
+    pub fn decide(&self, score: f32) -> Decision {
+        if score.is_finite() && score >= self.0 {
+            Decision::Accept
+        } else {
+            Decision::Reject
+        }
+    }
+}
+
+impl Default for VerifyThreshold {
+    fn default() -> Self {
+        Self(0.5)
+    }
+}
+
+impl FromStr for VerifyThreshold {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value
+            .parse::<f32>()
+            .map_err(|err| format!("invalid threshold '{value}': {err}"))?;
+        Self::new(value)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DecisionRange {
+    pub min: f32,
+    pub max: f32,
+    pub decision: Decision,
+}
+
+#[derive(Debug, Error)]
+pub enum DecisionTableError {
+    #[error("failed to read decision table: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse decision table: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("decision table must define a '{DEFAULT_SEGMENT}' segment")]
+    MissingDefault,
+    #[error("segment '{segment}' is invalid: {reason}")]
+    InvalidSegment { segment: String, reason: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct DecisionTable {
+    segments: HashMap<String, Vec<DecisionRange>>,
+}
+
+impl DecisionTable {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DecisionTableError> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, DecisionTableError> {
+        let mut segments: HashMap<String, Vec<DecisionRange>> = serde_json::from_str(json)?;
+        if !segments.contains_key(DEFAULT_SEGMENT) {
+            return Err(DecisionTableError::MissingDefault);
+        }
+
+        for (segment, ranges) in segments.iter_mut() {
+            ranges.sort_by(|a, b| a.min.total_cmp(&b.min));
+            validate_ranges(ranges).map_err(|reason| DecisionTableError::InvalidSegment {
+                segment: segment.clone(),
+                reason,
+            })?;
+        }
+
+        Ok(Self { segments })
+    }
+
+    pub fn has_segment(&self, segment: &str) -> bool {
+        self.segments.contains_key(segment)
+    }
+
+    // Ranges are half-open except the last, which also owns a score of exactly 1.0.
+    // A score that is not finite matches no range, so it cannot fall through
+    // to the last one.
+    pub fn decide(&self, segment: &str, score: f32) -> Option<Decision> {
+        if !score.is_finite() {
+            return None;
+        }
+        let segment = if segment.is_empty() {
+            DEFAULT_SEGMENT
+        } else {
+            segment
+        };
+        let ranges = self.segments.get(segment)?;
+        let score = score.clamp(0.0, 1.0);
+
+        ranges
+            .iter()
+            .find(|range| score >= range.min && score < range.max)
+            .or_else(|| ranges.last())
+            .map(|range| range.decision)
+    }
+}
+
+fn validate_ranges(ranges: &[DecisionRange]) -> Result<(), String> {
+    let (first, last) = match (ranges.first(), ranges.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Err("no ranges defined".to_string()),
+    };
+    if first.min != 0.0 || last.max != 1.0 {
+        return Err("ranges must cover [0, 1]".to_string());
+    }
+
+    for range in ranges {
+        if range.min >= range.max {
+            return Err(format!("range [{}, {}) is empty", range.min, range.max));
+        }
+    }
+    for pair in ranges.windows(2) {
+        if pair[1].min < pair[0].max {
+            return Err(format!(
+                "range starting at {} overlaps the range ending at {}",
+                pair[1].min, pair[0].max
+            ));
+        }
+        if pair[1].min > pair[0].max {
+            return Err(format!("gap between {} and {}", pair[0].max, pair[1].min));
+        }
+    }
+
+    Ok(())
+}
+
+impl From<Decision> for crate::verify::VerificationDecision {
+    fn from(decision: Decision) -> Self {
+        match decision {
+            Decision::Accept => Self::Accept,
+            Decision::Review => Self::Review,
+            Decision::Reject => Self::Reject,
+        }
+    }
+}
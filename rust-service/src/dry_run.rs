@@ -0,0 +1,27 @@
+use crate::{image::TensorLayout, ImageTensor};
+
+// Stand-in for inference when DRY_RUN is set: the mean of the first channel of
+// each batch element, so the decode and resize path can be exercised without a
+// Triton and the same image always gets the same score.
+pub fn scores(tensor: &ImageTensor, layout: TensorLayout) -> Vec<f32> {
+    let batch = tensor.shape.first().copied().unwrap_or(1).max(1) as usize;
+    let channels = match layout {
+        TensorLayout::Nchw => tensor.shape.get(1),
+        TensorLayout::Nhwc => tensor.shape.last(),
+    }
+    .copied()
+    .unwrap_or(1)
+    .max(1) as usize;
+
+    tensor
+        .data
+        .chunks(tensor.data.len() / batch)
+        .map(|element| {
+            let first: Vec<f32> = match layout {
+                TensorLayout::Nchw => element[..element.len() / channels].to_vec(),
+                TensorLayout::Nhwc => element.iter().step_by(channels).copied().collect(),
+            };
+            first.iter().sum::<f32>() / first.len().max(1) as f32
+        })
+        .collect()
+}
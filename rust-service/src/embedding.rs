@@ -0,0 +1,117 @@
+use std::path::Path;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ProjectionError {
+    #[error("failed to read projection matrix: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse projection matrix: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("projection matrix is invalid: {0}")]
+    Invalid(String),
+    #[error("projection expects {expected}-dim embeddings, model returned {actual}")]
+    DimensionMismatch { expected: usize, actual: usize },
+}
+
+pub fn l2_normalize(embedding: &[f32]) -> Vec<f32> {
+    let norm = embedding
+        .iter()
+        .map(|value| value * value)
+        .sum::<f32>()
+        .sqrt();
+    if norm == 0.0 {
+        return embedding.to_vec();
+    }
+    embedding.iter().map(|value| value / norm).collect()
+}
+
+// None when the dimensions differ or either vector is all zeros.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f32> {
+    if a.len() != b.len() {
+        return None;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|y| y * y).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return None;
+    }
+    Some((dot / (norm_a * norm_b)).clamp(-1.0, 1.0))
+}
+
+// Cosine distance in [0, 2]; 0 means the embeddings point the same way.
+pub fn drift(current: &[f32], enrolled: &[f32]) -> Option<f32> {
+    cosine_similarity(current, enrolled).map(|similarity| 1.0 - similarity)
+}
+
+// Symmetric int8 quantization of an L2-normalized vector, whose components
+// all lie within [-1, 1]. Each byte is the two's-complement i8 value.
+pub fn quantize_i8(normalized: &[f32]) -> Vec<u8> {
+    normalized
+        .iter()
+        .map(|value| (value.clamp(-1.0, 1.0) * 127.0).round() as i8 as u8)
+        .collect()
+}
+
+// Fixed linear projection (e.g. PCA) stored as output_dim rows of input_dim
+// weights, loaded once at startup.
+#[derive(Debug, Clone)]
+pub struct Projection {
+    rows: Vec<Vec<f32>>,
+}
+
+impl Projection {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ProjectionError> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ProjectionError> {
+        Self::new(serde_json::from_str(json)?)
+    }
+
+    pub fn new(rows: Vec<Vec<f32>>) -> Result<Self, ProjectionError> {
+        let input_dim = rows.first().map_or(0, Vec::len);
+        if input_dim == 0 {
+            return Err(ProjectionError::Invalid("matrix is empty".into()));
+        }
+        if let Some(row) = rows.iter().position(|row| row.len() != input_dim) {
+            return Err(ProjectionError::Invalid(format!(
+                "row {row} has {} columns, expected {input_dim}",
+                rows[row].len()
+            )));
+        }
+
+        Ok(Self { rows })
+    }
+
+    pub fn input_dim(&self) -> usize {
+        self.rows[0].len()
+    }
+
+    pub fn output_dim(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn rows(&self) -> &[Vec<f32>] {
+        &self.rows
+    }
+
+    // The reduced vector is re-normalized so cosine similarity still works.
+    pub fn apply(&self, embedding: &[f32]) -> Result<Vec<f32>, ProjectionError> {
+        if embedding.len() != self.input_dim() {
+            return Err(ProjectionError::DimensionMismatch {
+                expected: self.input_dim(),
+                actual: embedding.len(),
+            });
+        }
+
+        let reduced: Vec<f32> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().zip(embedding).map(|(w, v)| w * v).sum())
+            .collect();
+        Ok(l2_normalize(&reduced))
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    time::Duration,
+};
+
+const SMOOTHING: f32 = 0.2;
+const HEALTHY_SUCCESS_RATE: f32 = 0.8;
+const LOCAL_PREFERENCE: f64 = 10.0;
+// Keeps a failing endpoint in rotation at a trickle so it can recover.
+const MIN_WEIGHT: f64 = 0.05;
+const DEFAULT_LATENCY: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone)]
+pub struct EndpointStatus {
+    pub endpoint: String,
+    pub local: bool,
+    pub success_rate: f32,
+    pub latency: Option<Duration>,
+    pub weight: f64,
+}
+
+// Rolling success rate and latency, both exponentially weighted. Updates are
+// plain load/store pairs: a lost update under contention is harmless here.
+#[derive(Debug)]
+pub struct EndpointHealth {
+    success_rate: AtomicU32,
+    latency_micros: AtomicU64,
+}
+
+impl Default for EndpointHealth {
+    fn default() -> Self {
+        Self {
+            success_rate: AtomicU32::new(1.0f32.to_bits()),
+            latency_micros: AtomicU64::new(0),
+        }
+    }
+}
+
+impl EndpointHealth {
+    pub fn record(&self, success: bool, latency: Duration) {
+        let observed = if success { 1.0 } else { 0.0 };
+        let rate = self.success_rate();
+        let updated = rate + SMOOTHING * (observed - rate);
+        self.success_rate
+            .store(updated.to_bits(), Ordering::Relaxed);
+
+        if success {
+            let sample = latency.as_micros() as u64;
+            let previous = self.latency_micros.load(Ordering::Relaxed);
+            let updated = if previous == 0 {
+                sample
+            } else {
+                (previous as f64 + SMOOTHING as f64 * (sample as f64 - previous as f64)) as u64
+            };
+            self.latency_micros.store(updated.max(1), Ordering::Relaxed);
+        }
+    }
+
+    pub fn success_rate(&self) -> f32 {
+        f32::from_bits(self.success_rate.load(Ordering::Relaxed))
+    }
+
+    pub fn latency(&self) -> Option<Duration> {
+        match self.latency_micros.load(Ordering::Relaxed) {
+            0 => None,
+            micros => Some(Duration::from_micros(micros)),
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.success_rate() >= HEALTHY_SUCCESS_RATE
+    }
+
+    pub fn weight(&self, local: bool) -> f64 {
+        let latency_ms = self.latency().unwrap_or(DEFAULT_LATENCY).as_secs_f64() * 1000.0;
+        let rate = self.success_rate() as f64;
+        let preference = if local { LOCAL_PREFERENCE } else { 1.0 };
+
+        (rate * rate * preference / latency_ms.max(1.0)).max(MIN_WEIGHT / latency_ms.max(1.0))
+    }
+}
+
+// Maps a uniform sample in [0, 1) onto an index with probability
+// proportional to its weight.
+pub fn select_weighted(weights: &[f64], sample: f64) -> usize {
+    let total: f64 = weights.iter().sum();
+    if weights.is_empty() || total <= 0.0 {
+        return 0;
+    }
+
+    let mut remaining = sample.clamp(0.0, 1.0) * total;
+    for (index, weight) in weights.iter().enumerate() {
+        if remaining < *weight {
+            return index;
+        }
+        remaining -= weight;
+    }
+    weights.len() - 1
+}
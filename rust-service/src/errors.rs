@@ -0,0 +1,123 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use tonic::{Code, Status};
+use tonic_types::{ErrorDetails, FieldViolation, StatusExt};
+use tracing::warn;
+
+// Domain of every google.rpc.ErrorInfo this service attaches.
+pub const ERROR_DOMAIN: &str = "verify.ImageProcessor";
+
+// Machine-readable failure reasons, so clients can tell a Triton outage from a
+// broken deployment without parsing messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    TritonUnavailable,
+    TritonMisconfigured,
+    TritonInvalidResponse,
+    TritonDeadlineExceeded,
+}
+
+impl ErrorCode {
+    pub fn reason(self) -> &'static str {
+        match self {
+            Self::TritonUnavailable => "TRITON_UNAVAILABLE",
+            Self::TritonMisconfigured => "TRITON_MISCONFIGURED",
+            Self::TritonInvalidResponse => "TRITON_INVALID_RESPONSE",
+            Self::TritonDeadlineExceeded => "TRITON_DEADLINE_EXCEEDED",
+        }
+    }
+
+    pub fn status_code(self) -> Code {
+        match self {
+            Self::TritonUnavailable => Code::Unavailable,
+            Self::TritonMisconfigured => Code::FailedPrecondition,
+            Self::TritonInvalidResponse => Code::Internal,
+            Self::TritonDeadlineExceeded => Code::DeadlineExceeded,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorVerbosity {
+    #[default]
+    Public,
+    Debug,
+}
+
+impl FromStr for ErrorVerbosity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "public" => Ok(Self::Public),
+            "debug" => Ok(Self::Debug),
+            other => Err(format!("unknown error verbosity '{other}'")),
+        }
+    }
+}
+
+impl ErrorVerbosity {
+    // For messages written for clients; they carry no internal detail.
+    pub fn reject(self, code: Code, message: impl Into<String>) -> Status {
+        Status::new(code, message)
+    }
+
+    // The detail is always logged, but only reaches the client in debug mode.
+    pub fn fail(self, code: Code, public_message: &str, detail: impl Display) -> Status {
+        warn!(?code, %detail, "{public_message}");
+        match self {
+            Self::Public => Status::new(code, public_message),
+            Self::Debug => Status::new(code, format!("{public_message}: {detail}")),
+        }
+    }
+
+    // Like `fail`, with the status code taken from `code` and its reason
+    // attached as a google.rpc.ErrorInfo detail.
+    pub fn fail_with(self, code: ErrorCode, public_message: &str, detail: impl Display) -> Status {
+        let status = self.fail(code.status_code(), public_message, detail);
+        Status::with_error_details(
+            status.code(),
+            status.message(),
+            ErrorDetails::with_error_info(code.reason(), ERROR_DOMAIN, HashMap::new()),
+        )
+    }
+}
+
+// Collects every invalid field of a request so the client can fix them all in
+// one round-trip; the violations travel as a google.rpc.BadRequest detail.
+#[derive(Debug, Default)]
+pub struct FieldViolations(Vec<FieldViolation>);
+
+impl FieldViolations {
+    pub fn add(&mut self, field: &str, description: impl Into<String>) {
+        self.0.push(FieldViolation::new(field, description));
+    }
+
+    // Checked before any decoding so oversized uploads never reach the decoder.
+    pub fn check_size(&mut self, field: &str, len: usize, limit: usize) {
+        if len > limit {
+            self.add(
+                field,
+                format!("{field} is {len} bytes, exceeding the {limit} byte limit"),
+            );
+        }
+    }
+
+    pub fn into_result(self) -> Result<(), Status> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let message = self
+            .0
+            .iter()
+            .map(|violation| violation.description.as_str())
+            .collect::<Vec<_>>()
+            .join("; ");
+        Err(Status::with_error_details(
+            Code::InvalidArgument,
+            message,
+            ErrorDetails::with_bad_request(self.0),
+        ))
+    }
+}
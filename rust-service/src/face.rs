@@ -0,0 +1,136 @@
+use image::DynamicImage;
+use thiserror::Error;
+
+use crate::{
+    image::{tensor_from_image, InputSize, PreprocessConfig},
+    triton_client::{TritonClient, TritonError},
+};
+
+#[derive(Debug, Error)]
+pub enum FaceError {
+    #[error("no face detected")]
+    NotDetected,
+    #[error("face detection failed: {0}")]
+    Detection(#[from] TritonError),
+}
+
+// Corners as fractions of the image, so a box found on the detector's
+// downscaled copy applies to the full-resolution photo.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaceBox {
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+    pub confidence: f32,
+}
+
+impl FaceBox {
+    // Pixel (x, y, width, height) of the box grown by `margin` times its size
+    // on every side, clipped to the image; None when nothing is left.
+    pub fn crop_rect(&self, width: u32, height: u32, margin: f32) -> Option<(u32, u32, u32, u32)> {
+        let pad_x = (self.x2 - self.x1) * margin;
+        let pad_y = (self.y2 - self.y1) * margin;
+        let scale = |value: f32, size: u32| value.clamp(0.0, 1.0) * size as f32;
+        let left = scale(self.x1 - pad_x, width).floor() as u32;
+        let top = scale(self.y1 - pad_y, height).floor() as u32;
+        let right = scale(self.x2 + pad_x, width).ceil() as u32;
+        let bottom = scale(self.y2 + pad_y, height).ceil() as u32;
+        (right > left && bottom > top).then(|| (left, top, right - left, bottom - top))
+    }
+}
+
+// Separate Triton detection model run ahead of verification, for callers that
+// send full-scene photos. Its output is read as rows of
+// [x1, y1, x2, y2, confidence], with coordinates relative to the detector
+// input: the whole image stretched to `input_size`.
+#[derive(Clone)]
+pub struct FaceDetector {
+    triton: TritonClient,
+    input: PreprocessConfig,
+    min_confidence: f32,
+    margin: f32,
+}
+
+impl FaceDetector {
+    pub fn new(triton: TritonClient, input_size: InputSize) -> Self {
+        Self {
+            triton,
+            input: PreprocessConfig {
+                input_size,
+                ..Default::default()
+            },
+            min_confidence: 0.5,
+            margin: 0.2,
+        }
+    }
+
+    pub fn with_min_confidence(mut self, min_confidence: f32) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    pub fn with_margin(mut self, margin: f32) -> Self {
+        self.margin = margin.max(0.0);
+        self
+    }
+
+    pub fn triton(&self) -> &TritonClient {
+        &self.triton
+    }
+
+    pub fn input(&self) -> &PreprocessConfig {
+        &self.input
+    }
+
+    pub fn min_confidence(&self) -> f32 {
+        self.min_confidence
+    }
+
+    pub fn margin(&self) -> f32 {
+        self.margin
+    }
+
+    // The highest-confidence box at or above the minimum confidence.
+    pub async fn detect(
+        &self,
+        image: &DynamicImage,
+        request_id: &str,
+    ) -> Result<Option<FaceBox>, TritonError> {
+        let tensor = tensor_from_image(image, &self.input);
+        let output = self.triton.infer_with_id(&tensor, request_id).await?;
+        if output.len() % 5 != 0 {
+            return Err(TritonError::InvalidResponse(format!(
+                "face detector returned {} values, expected rows of 5",
+                output.len()
+            )));
+        }
+
+        Ok(output
+            .chunks_exact(5)
+            .map(|row| FaceBox {
+                x1: row[0],
+                y1: row[1],
+                x2: row[2],
+                y2: row[3],
+                confidence: row[4],
+            })
+            .filter(|face| face.confidence >= self.min_confidence)
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence)))
+    }
+
+    pub async fn crop(
+        &self,
+        image: &DynamicImage,
+        request_id: &str,
+    ) -> Result<DynamicImage, FaceError> {
+        let face = self
+            .detect(image, request_id)
+            .await?
+            .ok_or(FaceError::NotDetected)?;
+        let (x, y, width, height) = face
+            .crop_rect(image.width(), image.height(), self.margin)
+            .ok_or(FaceError::NotDetected)?;
+        Ok(image.crop_imm(x, y, width, height))
+    }
+}
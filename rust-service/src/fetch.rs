@@ -0,0 +1,279 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::{
+    header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    redirect, StatusCode, Url,
+};
+use thiserror::Error;
+use tonic::Code;
+
+#[derive(Debug, Error)]
+pub enum FetchError {
+    #[error("unsupported image URI scheme in '{0}'")]
+    UnsupportedScheme(String),
+    #[error("image request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("image server responded with status {0}")]
+    Status(StatusCode),
+    #[error("image exceeds the {limit} byte fetch limit")]
+    TooLarge { limit: usize },
+    #[error("image path '{0}' is outside the allowed file root")]
+    OutsideRoot(String),
+    #[error("image host in '{0}' is not on the allowed host list")]
+    HostNotAllowed(String),
+    #[error("timed out reading image file")]
+    Timeout,
+    #[error("failed to read image file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl FetchError {
+    // Refusals of the URI itself are the caller's to fix; anything else is the
+    // origin or the file system failing.
+    pub fn status_code(&self) -> Code {
+        match self {
+            Self::UnsupportedScheme(_)
+            | Self::OutsideRoot(_)
+            | Self::HostNotAllowed(_)
+            | Self::TooLarge { .. } => Code::InvalidArgument,
+            Self::Io(io) if io.kind() == std::io::ErrorKind::NotFound => Code::NotFound,
+            _ => Code::Unavailable,
+        }
+    }
+}
+
+// Whatever the origin gave us to revalidate a cached copy with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+pub enum Fetched<T> {
+    // The origin confirmed the cached copy is current; nothing was downloaded.
+    Cached(Arc<T>),
+    Fresh {
+        bytes: Vec<u8>,
+        validators: Validators,
+    },
+}
+
+struct CacheEntry<T> {
+    validators: Validators,
+    value: Arc<T>,
+}
+
+// Preprocessing results keyed by URL plus a caller-chosen variant, because the
+// same image yields a different tensor under another rotation or crop mode.
+// Entries are evicted oldest-first once `capacity` is reached.
+pub struct ImageFetcher<T> {
+    client: reqwest::Client,
+    timeout: Duration,
+    max_bytes: Option<usize>,
+    // file:// URIs are refused unless a root is configured, and then only
+    // resolve to files beneath it.
+    file_root: Option<PathBuf>,
+    // Likewise remote URIs, which would otherwise let any caller make the
+    // server request arbitrary hosts.
+    allowed_hosts: Vec<String>,
+    // Plain http:// is refused unless explicitly allowed, e.g. for an origin
+    // on a trusted network.
+    allow_http: bool,
+    capacity: usize,
+    cache: Mutex<ResultCache<T>>,
+}
+
+struct ResultCache<T> {
+    entries: HashMap<String, CacheEntry<T>>,
+    order: VecDeque<String>,
+}
+
+impl<T> ImageFetcher<T> {
+    pub fn new(timeout: Duration, capacity: usize) -> Result<Self, FetchError> {
+        Ok(Self {
+            // A redirect could leave the allowed hosts, so none are followed.
+            client: reqwest::Client::builder()
+                .timeout(timeout)
+                .redirect(redirect::Policy::none())
+                .build()?,
+            timeout,
+            max_bytes: None,
+            file_root: None,
+            allowed_hosts: Vec::new(),
+            allow_http: false,
+            capacity,
+            cache: Mutex::new(ResultCache {
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+        })
+    }
+
+    pub fn with_max_bytes(mut self, limit: usize) -> Self {
+        self.max_bytes = Some(limit);
+        self
+    }
+
+    pub fn with_file_root(mut self, root: impl Into<PathBuf>) -> Result<Self, FetchError> {
+        self.file_root = Some(std::fs::canonicalize(root.into())?);
+        Ok(self)
+    }
+
+    pub fn with_allowed_hosts<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.allowed_hosts = hosts
+            .into_iter()
+            .map(|host| host.as_ref().trim().to_ascii_lowercase())
+            .filter(|host| !host.is_empty())
+            .collect();
+        self
+    }
+
+    pub fn with_plain_http(mut self, allow: bool) -> Self {
+        self.allow_http = allow;
+        self
+    }
+
+    pub async fn fetch(&self, url: &str, variant: &str) -> Result<Fetched<T>, FetchError> {
+        if let Some(path) = url.strip_prefix("file://") {
+            let bytes = tokio::time::timeout(self.timeout, self.read_file(url, path))
+                .await
+                .map_err(|_| FetchError::Timeout)??;
+            return Ok(Fetched::Fresh {
+                bytes,
+                validators: Validators::default(),
+            });
+        }
+        if !(url.starts_with("https://") || (self.allow_http && url.starts_with("http://"))) {
+            return Err(FetchError::UnsupportedScheme(url.to_string()));
+        }
+        if !self.host_allowed(url) {
+            return Err(FetchError::HostNotAllowed(url.to_string()));
+        }
+
+        let key = cache_key(url, variant);
+        let cached = self.lookup(&key);
+        let mut request = self.client.get(url);
+        if let Some((validators, _)) = &cached {
+            if let Some(etag) = &validators.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let mut response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some((_, value)) = cached {
+                return Ok(Fetched::Cached(value));
+            }
+        }
+        if !response.status().is_success() {
+            return Err(FetchError::Status(response.status()));
+        }
+
+        let header = |name: HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+        if let (Some(limit), Some(length)) = (self.max_bytes, response.content_length()) {
+            if length > limit as u64 {
+                return Err(FetchError::TooLarge { limit });
+            }
+        }
+        // Content-Length is optional, so the limit is enforced while reading too.
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+            self.check_size(bytes.len())?;
+        }
+
+        Ok(Fetched::Fresh { bytes, validators })
+    }
+
+    async fn read_file(&self, url: &str, path: &str) -> Result<Vec<u8>, FetchError> {
+        let Some(root) = &self.file_root else {
+            return Err(FetchError::UnsupportedScheme(url.to_string()));
+        };
+        // Canonicalizing resolves `..` and symlinks before the containment check.
+        let path = tokio::fs::canonicalize(path).await?;
+        if !path.starts_with(root) {
+            return Err(FetchError::OutsideRoot(url.to_string()));
+        }
+        let length = tokio::fs::metadata(&path).await?.len();
+        self.check_size(usize::try_from(length).unwrap_or(usize::MAX))?;
+        Ok(tokio::fs::read(&path).await?)
+    }
+
+    fn host_allowed(&self, url: &str) -> bool {
+        let Some(host) = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+        else {
+            return false;
+        };
+        self.allowed_hosts.contains(&host)
+    }
+
+    fn check_size(&self, len: usize) -> Result<(), FetchError> {
+        match self.max_bytes {
+            Some(limit) if len > limit => Err(FetchError::TooLarge { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    // Only responses carrying validators are kept: without them the cached
+    // result could never be confirmed fresh and would just occupy a slot.
+    pub fn store(&self, url: &str, variant: &str, validators: Validators, value: Arc<T>) {
+        if self.capacity == 0 || validators.is_empty() {
+            return;
+        }
+
+        let key = cache_key(url, variant);
+        let mut cache = self.cache.lock().expect("image cache lock poisoned");
+        if !cache.entries.contains_key(&key) {
+            while cache.entries.len() >= self.capacity {
+                let Some(oldest) = cache.order.pop_front() else {
+                    break;
+                };
+                cache.entries.remove(&oldest);
+            }
+            cache.order.push_back(key.clone());
+        }
+        cache.entries.insert(key, CacheEntry { validators, value });
+    }
+
+    fn lookup(&self, key: &str) -> Option<(Validators, Arc<T>)> {
+        let cache = self.cache.lock().expect("image cache lock poisoned");
+        cache
+            .entries
+            .get(key)
+            .map(|entry| (entry.validators.clone(), Arc::clone(&entry.value)))
+    }
+}
+
+fn cache_key(url: &str, variant: &str) -> String {
+    format!("{variant}\n{url}")
+}
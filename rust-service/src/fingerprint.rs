@@ -0,0 +1,224 @@
+use crate::{
+    activation::Activation,
+    embedding::Projection,
+    face::FaceDetector,
+    image::{
+        ChannelMode, ChannelOrder, Normalization, PreprocessConfig, ResizeFilter, ResizeMode,
+        Rotation, TensorLayout, TruncationPolicy,
+    },
+    pipeline::PreprocessStep,
+    triton_client::TritonClient,
+};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// FNV-1a over explicitly encoded fields keeps the fingerprint stable across
+// builds and toolchains. Neither std's DefaultHasher nor Debug output promise
+// that, so every field is spelled out here and new settings must be added.
+pub fn config_fingerprint(
+    triton: &TritonClient,
+    preprocess: &PreprocessConfig,
+    activation: Activation,
+    detector: Option<&FaceDetector>,
+) -> String {
+    model_fingerprint(
+        triton,
+        preprocess,
+        activation,
+        detector,
+        triton.model_name(),
+        triton.model_version(),
+    )
+}
+
+// For a request that chose its own model or version, so its responses are
+// told apart from those of the configured model. An empty version is Triton's
+// latest.
+pub fn model_fingerprint(
+    triton: &TritonClient,
+    preprocess: &PreprocessConfig,
+    activation: Activation,
+    detector: Option<&FaceDetector>,
+    model: &str,
+    version: &str,
+) -> String {
+    let mut hash = Fnv(FNV_OFFSET_BASIS);
+    hash.str(model);
+    hash.str(version);
+    hash.str(triton.input_name());
+    hash.str(triton.input_dtype().as_str());
+    hash.str(triton.output_name());
+    hash.preprocess(preprocess);
+    hash.str(match activation {
+        Activation::None => "none",
+        Activation::Sigmoid => "sigmoid",
+        Activation::Softmax => "softmax",
+    });
+    // The detector decides which part of the photo is scored.
+    hash.option(detector, |hash, detector| {
+        let triton = detector.triton();
+        hash.str(triton.model_name());
+        hash.str(triton.model_version());
+        hash.str(triton.input_name());
+        hash.str(triton.input_dtype().as_str());
+        hash.str(triton.output_name());
+        hash.preprocess(detector.input());
+        hash.f32s(&[detector.min_confidence(), detector.margin()]);
+    });
+
+    format!("{:016x}", hash.0)
+}
+
+// Enrolled templates also depend on the embedding projection, so templates
+// reduced with different matrices, or none, are told apart.
+pub fn template_fingerprint(config_fingerprint: &str, projection: Option<&Projection>) -> String {
+    let mut hash = Fnv(FNV_OFFSET_BASIS);
+    hash.str(config_fingerprint);
+    hash.option(projection, |hash, projection| {
+        hash.u32(projection.output_dim() as u32);
+        hash.u32(projection.input_dim() as u32);
+        for row in projection.rows() {
+            hash.f32s(row);
+        }
+    });
+
+    format!("{:016x}", hash.0)
+}
+
+struct Fnv(u64);
+
+impl Fnv {
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    // NUL-terminated so adjacent strings cannot run together.
+    fn str(&mut self, value: &str) {
+        self.bytes(value.as_bytes());
+        self.bytes(&[0]);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f32s(&mut self, values: &[f32]) {
+        for value in values {
+            self.bytes(&value.to_le_bytes());
+        }
+    }
+
+    fn option<T>(&mut self, value: Option<T>, mut write: impl FnMut(&mut Self, T)) {
+        match value {
+            Some(value) => {
+                self.str("some");
+                write(self, value);
+            }
+            None => self.str("none"),
+        }
+    }
+
+    fn preprocess(&mut self, config: &PreprocessConfig) {
+        for format in config.allowed_formats.formats() {
+            self.str(format.extensions_str().first().copied().unwrap_or_default());
+        }
+        self.str("formats");
+        self.u32(config.input_size.width);
+        self.u32(config.input_size.height);
+        match config.resize {
+            ResizeMode::Stretch => self.str("stretch"),
+            ResizeMode::Letterbox { fill } => {
+                self.str("letterbox");
+                self.bytes(&fill);
+            }
+        }
+        self.str(match config.resize_filter {
+            ResizeFilter::Nearest => "nearest",
+            ResizeFilter::Triangle => "triangle",
+            ResizeFilter::CatmullRom => "catmull_rom",
+            ResizeFilter::Gaussian => "gaussian",
+            ResizeFilter::Lanczos3 => "lanczos3",
+        });
+        self.str(match config.channels {
+            ChannelMode::Rgb => "rgb",
+            ChannelMode::Grayscale => "grayscale",
+        });
+        self.str(match config.channel_order {
+            ChannelOrder::Rgb => "rgb",
+            ChannelOrder::Bgr => "bgr",
+        });
+        self.str(match config.layout {
+            TensorLayout::Nchw => "nchw",
+            TensorLayout::Nhwc => "nhwc",
+        });
+        self.bytes(&[u8::from(config.center_crop)]);
+        self.bytes(&config.background.0);
+        match config.normalization {
+            Normalization::Fixed { mean, std } => {
+                self.str("fixed");
+                self.f32s(&mean);
+                self.f32s(&std);
+            }
+            Normalization::PerImageStandardize => self.str("per_image_standardize"),
+        }
+        let quality = &config.quality;
+        self.option(quality.min_dimension, Self::u32);
+        self.option(quality.min_variance, |hash, value| hash.f32s(&[value]));
+        self.option(quality.max_aspect_ratio, |hash, value| hash.f32s(&[value]));
+        self.option(quality.min_blur_score, |hash, value| hash.f32s(&[value]));
+        self.str(match config.truncation {
+            TruncationPolicy::Strict => "strict",
+            TruncationPolicy::Lenient => "lenient",
+        });
+        self.option(config.memory_budget, |hash, value| {
+            hash.bytes(&value.to_le_bytes())
+        });
+        self.str(match config.rotation {
+            Rotation::None => "0",
+            Rotation::Clockwise90 => "90",
+            Rotation::Clockwise180 => "180",
+            Rotation::Clockwise270 => "270",
+        });
+        self.bytes(&[u8::from(config.downscale_only)]);
+        self.bytes(&[u8::from(config.prescale_jpeg)]);
+        self.option(config.pipeline.as_ref(), |hash, pipeline| {
+            for step in pipeline.steps() {
+                hash.step(step);
+            }
+        });
+    }
+
+    fn step(&mut self, step: &PreprocessStep) {
+        match step {
+            PreprocessStep::Crop {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                self.str("crop");
+                self.f32s(&[*x, *y, *width, *height]);
+            }
+            PreprocessStep::Rotate { degrees } => {
+                self.str("rotate");
+                self.u32(*degrees);
+            }
+            PreprocessStep::CenterCrop => self.str("center_crop"),
+            PreprocessStep::Resize { width, height } => {
+                self.str("resize");
+                self.u32(*width);
+                self.u32(*height);
+            }
+            PreprocessStep::ToTensor => self.str("to_tensor"),
+            PreprocessStep::Normalize { mean, std } => {
+                self.str("normalize");
+                self.f32s(mean);
+                self.f32s(std);
+            }
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use tonic_health::{server::HealthReporter, ServingStatus};
+
+pub const SERVICE_NAME: &str = "verify.ImageProcessor";
+
+// Verifications cannot succeed without Triton, so its reachability is the
+// service's health. Probes naming no service see the same status.
+pub async fn report_triton(reporter: &mut HealthReporter, reachable: bool) {
+    let status = if reachable {
+        ServingStatus::Serving
+    } else {
+        ServingStatus::NotServing
+    };
+    reporter.set_service_status(SERVICE_NAME, status).await;
+    reporter.set_service_status("", status).await;
+}
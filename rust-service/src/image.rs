@@ -1,5 +1,22 @@
-use image::{imageops::FilterType, DynamicImage, RgbImage};
+use std::{io::Cursor, str::FromStr};
+
+use image::{
+    codecs::{gif::GifDecoder, jpeg::JpegDecoder, png::PngDecoder},
+    error::{DecodingError, ImageFormatHint},
+    imageops::{self, FilterType},
+    io::Reader,
+    AnimationDecoder, ColorType, DynamicImage, GenericImageView, ImageBuffer, ImageDecoder,
+    ImageFormat, Rgb, RgbImage,
+};
+use serde::Deserialize;
 use thiserror::Error;
+use tracing::warn;
+
+use crate::{
+    face::FaceError,
+    pipeline::PreprocessPipeline,
+    quality::{QualityGates, QualityRejection},
+};
 
 #[derive(Debug, Clone)]
 pub struct ImageTensor {
@@ -11,26 +28,919 @@ pub struct ImageTensor {
 pub enum ImageError {
     #[error("image decoding failed: {0}")]
     Decode(#[from] image::ImageError),
+    #[error("image rejected by quality gate: {0}")]
+    LowQuality(#[from] QualityRejection),
+    #[error("image data is truncated: missing {0} end marker")]
+    Truncated(&'static str),
+    #[error("estimated memory {estimated} bytes exceeds the per-request budget of {budget} bytes")]
+    MemoryBudgetExceeded { estimated: u64, budget: u64 },
+    #[error("crop region '{0}' does not overlap the image")]
+    EmptyRegion(String),
+    #[error("image {index} in the batch failed: {source}")]
+    BatchItem {
+        index: usize,
+        source: Box<ImageError>,
+    },
+    #[error("batch contains no images")]
+    EmptyBatch,
+    #[error("image format {0} is not accepted")]
+    FormatNotAllowed(String),
+    #[error(transparent)]
+    Face(#[from] FaceError),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TruncationPolicy {
+    #[default]
+    Strict,
+    Lenient,
+}
+
+impl FromStr for TruncationPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "strict" => Ok(Self::Strict),
+            "lenient" => Ok(Self::Lenient),
+            other => Err(format!("unknown truncation policy '{other}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise90,
+    Clockwise180,
+    Clockwise270,
+}
+
+impl TryFrom<u32> for Rotation {
+    type Error = String;
+
+    fn try_from(degrees: u32) -> Result<Self, Self::Error> {
+        match degrees {
+            0 => Ok(Self::None),
+            90 => Ok(Self::Clockwise90),
+            180 => Ok(Self::Clockwise180),
+            270 => Ok(Self::Clockwise270),
+            other => Err(format!(
+                "rotation must be one of 0, 90, 180 or 270 degrees, got {other}"
+            )),
+        }
+    }
+}
+
+impl Rotation {
+    pub(crate) fn apply(&self, image: DynamicImage) -> DynamicImage {
+        match self {
+            Self::None => image,
+            Self::Clockwise90 => image.rotate90(),
+            Self::Clockwise180 => image.rotate180(),
+            Self::Clockwise270 => image.rotate270(),
+        }
+    }
+}
+
+// Spatial size of the model input tensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for InputSize {
+    fn default() -> Self {
+        Self {
+            width: 224,
+            height: 224,
+        }
+    }
+}
+
+// Keeps per-image standardization of a flat channel from dividing by zero.
+const STANDARDIZE_EPSILON: f32 = 1e-5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Normalization {
+    // Per-channel `(v / 255 - mean) / std`, or `v / 65535` for 16-bit sources,
+    // applied after the tensor is laid out.
+    Fixed { mean: [f32; 3], std: [f32; 3] },
+    // Each channel of each image shifted to zero mean and unit variance using
+    // its own statistics, computed over the resized image.
+    PerImageStandardize,
+}
+
+impl Normalization {
+    pub const IDENTITY: Self = Self::Fixed {
+        mean: [0.0; 3],
+        std: [1.0; 3],
+    };
+    pub const IMAGENET: Self = Self::Fixed {
+        mean: [0.485, 0.456, 0.406],
+        std: [0.229, 0.224, 0.225],
+    };
+    // `v / 127.5 - 1`, for models trained on [-1, 1] inputs.
+    pub const MINUS_ONE_TO_ONE: Self = Self::Fixed {
+        mean: [0.5; 3],
+        std: [0.5; 3],
+    };
+
+    // Single-channel tensors use the first mean and std.
+    pub fn apply(&self, tensor: &mut ImageTensor) {
+        let channels = tensor.shape.get(1).copied().unwrap_or(3);
+        let plane: i64 = tensor.shape.iter().skip(2).product();
+        self.apply_planes(&mut tensor.data, plane as usize, channels as usize);
+    }
+
+    // Planes repeat the channels in order for every item in the batch.
+    pub(crate) fn apply_planes(&self, data: &mut [f32], plane: usize, channels: usize) {
+        if *self == Self::IDENTITY {
+            return;
+        }
+        for (index, values) in data.chunks_mut(plane.max(1)).enumerate() {
+            let (mean, std) = match self {
+                Self::Fixed { mean, std } => {
+                    let channel = index % channels.clamp(1, 3);
+                    (mean[channel], std[channel])
+                }
+                Self::PerImageStandardize => {
+                    let count = values.len() as f32;
+                    let mean = values.iter().sum::<f32>() / count;
+                    let variance = values
+                        .iter()
+                        .map(|value| (value - mean).powi(2))
+                        .sum::<f32>()
+                        / count;
+                    (mean, variance.sqrt() + STANDARDIZE_EPSILON)
+                }
+            };
+            for value in values {
+                *value = (*value - mean) / std;
+            }
+        }
+    }
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl FromStr for Normalization {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "identity" | "none" => Ok(Self::IDENTITY),
+            "imagenet" => Ok(Self::IMAGENET),
+            "minus_one_to_one" => Ok(Self::MINUS_ONE_TO_ONE),
+            "per_image" => Ok(Self::PerImageStandardize),
+            other => Err(format!("unknown normalization '{other}'")),
+        }
+    }
+}
+
+// `R,G,B` with each channel in 0..=255.
+fn parse_rgb(value: &str) -> Result<[u8; 3], String> {
+    let channels = value
+        .split(',')
+        .map(|channel| channel.trim().parse::<u8>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("'{value}': {err}"))?;
+    <[u8; 3]>::try_from(channels).map_err(|_| format!("'{value}' must have three channels"))
+}
+
+// Colour transparent pixels are composited over before the alpha is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Background(pub [u8; 3]);
+
+impl Default for Background {
+    fn default() -> Self {
+        Self([255; 3])
+    }
+}
+
+impl FromStr for Background {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        parse_rgb(value)
+            .map(Self)
+            .map_err(|err| format!("invalid background: {err}"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResizeMode {
+    #[default]
+    Stretch,
+    // Scales the longest side to the target and pads the rest, centred.
+    Letterbox {
+        fill: [u8; 3],
+    },
+}
+
+impl FromStr for ResizeMode {
+    type Err = String;
+
+    // `stretch`, `letterbox` (black padding) or `letterbox:R,G,B`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let lowered = value.to_ascii_lowercase();
+        let (mode, fill) = match lowered.split_once(':') {
+            Some((mode, fill)) => (mode, Some(fill)),
+            None => (lowered.as_str(), None),
+        };
+        match (mode, fill) {
+            ("stretch", None) => Ok(Self::Stretch),
+            ("letterbox", None) => Ok(Self::Letterbox { fill: [0; 3] }),
+            ("letterbox", Some(fill)) => Ok(Self::Letterbox {
+                fill: parse_rgb(fill).map_err(|err| format!("invalid letterbox fill: {err}"))?,
+            }),
+            _ => Err(format!("unknown resize mode '{value}'")),
+        }
+    }
+}
+
+// Resampling filter for the resize to the model input. Lanczos3 keeps more
+// detail when downscaling large photos; Nearest is the cheapest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResizeFilter {
+    Nearest,
+    Triangle,
+    #[default]
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    pub fn filter_type(&self) -> FilterType {
+        match self {
+            Self::Nearest => FilterType::Nearest,
+            Self::Triangle => FilterType::Triangle,
+            Self::CatmullRom => FilterType::CatmullRom,
+            Self::Gaussian => FilterType::Gaussian,
+            Self::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+impl FromStr for ResizeFilter {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "nearest" => Ok(Self::Nearest),
+            "triangle" | "bilinear" => Ok(Self::Triangle),
+            "catmull_rom" | "catmullrom" | "bicubic" => Ok(Self::CatmullRom),
+            "gaussian" => Ok(Self::Gaussian),
+            "lanczos3" | "lanczos" => Ok(Self::Lanczos3),
+            other => Err(format!("unknown resize filter '{other}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChannelMode {
+    #[default]
+    Rgb,
+    // One Rec. 601 luminance plane.
+    Grayscale,
+}
+
+impl ChannelMode {
+    pub fn channels(&self) -> i64 {
+        match self {
+            Self::Rgb => 3,
+            Self::Grayscale => 1,
+        }
+    }
+}
+
+impl FromStr for ChannelMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "rgb" => Ok(Self::Rgb),
+            "grayscale" | "gray" | "luma" => Ok(Self::Grayscale),
+            other => Err(format!("unknown channel mode '{other}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChannelOrder {
+    #[default]
+    Rgb,
+    // OpenCV-trained models. The planes are swapped after normalization, so
+    // mean and std are still given in R, G, B order.
+    Bgr,
+}
+
+impl FromStr for ChannelOrder {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "rgb" => Ok(Self::Rgb),
+            "bgr" => Ok(Self::Bgr),
+            other => Err(format!("unknown channel order '{other}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TensorLayout {
+    #[default]
+    Nchw,
+    // Channels interleaved per pixel, as TensorFlow models expect.
+    Nhwc,
+}
+
+impl FromStr for TensorLayout {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "nchw" => Ok(Self::Nchw),
+            "nhwc" => Ok(Self::Nhwc),
+            other => Err(format!("unknown tensor layout '{other}'")),
+        }
+    }
+}
+
+// Formats the preprocessing pipeline has been validated against. Detection
+// uses the file signature, so a mislabelled upload is judged by its content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowedFormats(Vec<ImageFormat>);
+
+impl AllowedFormats {
+    pub fn new(formats: Vec<ImageFormat>) -> Self {
+        Self(formats)
+    }
+
+    pub fn formats(&self) -> &[ImageFormat] {
+        &self.0
+    }
+
+    pub fn check(&self, bytes: &[u8]) -> Result<ImageFormat, ImageError> {
+        match image::guess_format(bytes) {
+            Ok(format) if self.0.contains(&format) => Ok(format),
+            Ok(format) => Err(ImageError::FormatNotAllowed(format!("{format:?}"))),
+            Err(_) => Err(ImageError::FormatNotAllowed("unrecognized".to_string())),
+        }
+    }
+}
+
+impl Default for AllowedFormats {
+    fn default() -> Self {
+        Self(vec![ImageFormat::Jpeg, ImageFormat::Png])
+    }
+}
+
+impl FromStr for AllowedFormats {
+    type Err = String;
+
+    // Comma-separated file extensions, e.g. "jpeg,png,webp".
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|extension| !extension.is_empty())
+            .map(|extension| {
+                ImageFormat::from_extension(extension)
+                    .ok_or_else(|| format!("unknown image format '{extension}'"))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PreprocessConfig {
+    pub allowed_formats: AllowedFormats,
+    pub input_size: InputSize,
+    pub resize: ResizeMode,
+    pub resize_filter: ResizeFilter,
+    pub channels: ChannelMode,
+    pub channel_order: ChannelOrder,
+    pub layout: TensorLayout,
+    // Crop to a centred square of the shorter side before resizing.
+    pub center_crop: bool,
+    pub background: Background,
+    // Not applied on top of a custom pipeline, which has its own Normalize step.
+    pub normalization: Normalization,
+    pub quality: QualityGates,
+    pub truncation: TruncationPolicy,
+    pub memory_budget: Option<u64>,
+    pub rotation: Rotation,
+    pub downscale_only: bool,
+    // Decode JPEGs at a reduced DCT scale close to the model input instead of
+    // at full resolution. Crop regions then work from the reduced image.
+    pub prescale_jpeg: bool,
+    // Replaces the default resize-and-scale step for single-image requests.
+    pub pipeline: Option<PreprocessPipeline>,
+}
+
+impl PreprocessConfig {
+    // Shape of a single-image tensor, batch dimension included. A pipeline
+    // always emits NCHW RGB; -1 marks a size that follows the uploaded image.
+    pub fn tensor_shape(&self) -> Vec<i64> {
+        if let Some(pipeline) = &self.pipeline {
+            let (width, height) = pipeline
+                .output_size()
+                .map_or((-1, -1), |(width, height)| (width.into(), height.into()));
+            return vec![1, 3, height, width];
+        }
+        let channels = self.channels.channels();
+        let height = self.input_size.height as i64;
+        let width = self.input_size.width as i64;
+        match self.layout {
+            TensorLayout::Nchw => vec![1, channels, height, width],
+            TensorLayout::Nhwc => vec![1, height, width, channels],
+        }
+    }
+}
+
+// Rectangle expressed as fractions of the source image so one definition
+// works across scan resolutions.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CropRegion {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl CropRegion {
+    pub(crate) fn crop(&self, image: &DynamicImage) -> Result<DynamicImage, ImageError> {
+        let (width, height) = image.dimensions();
+        let left = (self.x.clamp(0.0, 1.0) * width as f32).round() as u32;
+        let top = (self.y.clamp(0.0, 1.0) * height as f32).round() as u32;
+        let right = ((self.x + self.width).clamp(0.0, 1.0) * width as f32).round() as u32;
+        let bottom = ((self.y + self.height).clamp(0.0, 1.0) * height as f32).round() as u32;
+
+        if right <= left || bottom <= top {
+            return Err(ImageError::EmptyRegion(self.name.clone()));
+        }
+
+        Ok(image.crop_imm(left, top, right - left, bottom - top))
+    }
 }
 
 pub fn preprocess(bytes: &[u8]) -> Result<ImageTensor, ImageError> {
-    let img = image::load_from_memory(bytes)?;
-    let resized = resize_image(&img);
-    let rgb = resized.to_rgb8();
+    preprocess_with(bytes, &PreprocessConfig::default())
+}
+
+pub fn preprocess_with(bytes: &[u8], config: &PreprocessConfig) -> Result<ImageTensor, ImageError> {
+    let img = decode(bytes, config)?;
+    match &config.pipeline {
+        Some(pipeline) => pipeline.run(&img),
+        None => Ok(tensor_from_image(&img, config)),
+    }
+}
+
+// What preprocessing did to one image, for debugging score discrepancies.
+// There is no EXIF orientation handling; `rotation` is the configured one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreprocessMeta {
+    // As stored in the file header, before any JPEG prescale or rotation.
+    pub original_width: u32,
+    pub original_height: u32,
+    pub rotation: Rotation,
+    pub resize: ResizeMode,
+    pub center_crop: bool,
+    // A configured pipeline replaced the resize, crop and normalization above.
+    pub custom_pipeline: bool,
+    pub shape: Vec<i64>,
+}
+
+pub fn preprocess_with_meta(
+    bytes: &[u8],
+    config: &PreprocessConfig,
+) -> Result<(ImageTensor, PreprocessMeta), ImageError> {
+    let tensor = preprocess_with(bytes, config)?;
+    let (original_width, original_height) = image_dimensions(bytes)?;
+    let meta = PreprocessMeta {
+        original_width,
+        original_height,
+        rotation: config.rotation,
+        resize: config.resize,
+        center_crop: config.center_crop,
+        custom_pipeline: config.pipeline.is_some(),
+        shape: tensor.shape.clone(),
+    };
+    Ok((tensor, meta))
+}
+
+pub fn preprocess_batch(images: &[&[u8]]) -> Result<ImageTensor, ImageError> {
+    preprocess_batch_with(images, &PreprocessConfig::default())
+}
+
+// Each image goes through preprocess_with and the results are stacked along
+// the batch dimension.
+pub fn preprocess_batch_with(
+    images: &[&[u8]],
+    config: &PreprocessConfig,
+) -> Result<ImageTensor, ImageError> {
+    let mut shape = None;
+    let mut data = Vec::new();
+    for (index, bytes) in images.iter().enumerate() {
+        let tensor = preprocess_with(bytes, config).map_err(|err| ImageError::BatchItem {
+            index,
+            source: Box::new(err),
+        })?;
+        data.extend(tensor.data);
+        shape.get_or_insert(tensor.shape);
+    }
+
+    let mut shape = shape.ok_or(ImageError::EmptyBatch)?;
+    shape[0] = images.len() as i64;
+    Ok(ImageTensor { shape, data })
+}
+
+pub fn preprocess_regions(
+    bytes: &[u8],
+    config: &PreprocessConfig,
+    regions: &[CropRegion],
+) -> Result<ImageTensor, ImageError> {
+    let img = decode(bytes, config)?;
+    tensor_from_regions(&img, regions, config)
+}
+
+// Resize, layout and normalization from `config`; a configured pipeline is
+// not consulted here.
+pub fn tensor_from_image(image: &DynamicImage, config: &PreprocessConfig) -> ImageTensor {
+    let size = config.input_size;
+    let mut tensor = ImageTensor {
+        shape: vec![
+            1,
+            config.channels.channels(),
+            i64::from(size.height),
+            i64::from(size.width),
+        ],
+        data: image_to_tensor(image, config),
+    };
+    config.normalization.apply(&mut tensor);
+    into_layout(
+        into_channel_order(tensor, config.channel_order),
+        config.layout,
+    )
+}
+
+pub fn tensor_from_regions(
+    image: &DynamicImage,
+    regions: &[CropRegion],
+    config: &PreprocessConfig,
+) -> Result<ImageTensor, ImageError> {
+    let size = config.input_size;
+    let channels = config.channels.channels();
+    let mut data = Vec::with_capacity(
+        regions.len() * channels as usize * size.width as usize * size.height as usize,
+    );
+    for region in regions {
+        let cropped = region.crop(image)?;
+        data.extend(image_to_tensor(&cropped, config));
+    }
+
+    let mut tensor = ImageTensor {
+        shape: vec![
+            regions.len() as i64,
+            channels,
+            i64::from(size.height),
+            i64::from(size.width),
+        ],
+        data,
+    };
+    config.normalization.apply(&mut tensor);
+    Ok(into_layout(
+        into_channel_order(tensor, config.channel_order),
+        config.layout,
+    ))
+}
 
-    let data = to_chw_tensor(&rgb);
+// Swaps the red and blue planes of every item in a channel-first tensor;
+// single-channel tensors are left alone.
+fn into_channel_order(mut tensor: ImageTensor, order: ChannelOrder) -> ImageTensor {
+    let (ChannelOrder::Bgr, &[_, 3, height, width]) = (order, &tensor.shape[..]) else {
+        return tensor;
+    };
+    let plane = (height * width) as usize;
+    for chw in tensor.data.chunks_mut((plane * 3).max(1)) {
+        let (red, rest) = chw.split_at_mut(plane);
+        red.swap_with_slice(&mut rest[plane..]);
+    }
+    tensor
+}
+
+// Tensors are built channel-first and normalized before this, so only the
+// final ordering depends on the layout.
+fn into_layout(tensor: ImageTensor, layout: TensorLayout) -> ImageTensor {
+    let TensorLayout::Nhwc = layout else {
+        return tensor;
+    };
+    let [batch, channels, height, width] = tensor.shape[..] else {
+        return tensor;
+    };
+
+    let plane = (height * width) as usize;
+    let item = plane * channels as usize;
+    let mut data = Vec::with_capacity(tensor.data.len());
+    for chw in tensor.data.chunks(item.max(1)) {
+        for pixel in 0..plane {
+            data.extend((0..channels as usize).map(|channel| chw[channel * plane + pixel]));
+        }
+    }
 
-    Ok(ImageTensor {
-        shape: vec![1, 3, 224, 224],
+    ImageTensor {
+        shape: vec![batch, height, width, channels],
         data,
-    })
+    }
+}
+
+pub fn decode(bytes: &[u8], config: &PreprocessConfig) -> Result<DynamicImage, ImageError> {
+    config.allowed_formats.check(bytes)?;
+    if config.truncation == TruncationPolicy::Strict {
+        check_end_marker(bytes)?;
+    }
+    if let Some(budget) = config.memory_budget {
+        let estimated = estimate_memory(bytes, config.input_size)?;
+        if estimated > budget {
+            return Err(ImageError::MemoryBudgetExceeded { estimated, budget });
+        }
+    }
+    let img = config
+        .rotation
+        .apply(flatten_alpha(load(bytes, config)?, config.background));
+    config.quality.check(&img)?;
+    let size = config.input_size;
+    config.quality.check_blur(&img, size.width, size.height)?;
+    if config.downscale_only {
+        check_no_upscale(&img, config.input_size)?;
+    }
+
+    Ok(img)
+}
+
+fn load(bytes: &[u8], config: &PreprocessConfig) -> Result<DynamicImage, ImageError> {
+    if image::guess_format(bytes).ok() == Some(ImageFormat::Gif) {
+        return first_frame(GifDecoder::new(Cursor::new(bytes))?, ImageFormat::Gif);
+    }
+    if !config.prescale_jpeg || image::guess_format(bytes).ok() != Some(ImageFormat::Jpeg) {
+        return Ok(image::load_from_memory(bytes)?);
+    }
+
+    // The decoder picks the smallest 1/8..8/8 scale covering the request, so
+    // the result never drops below the model input or the quality gate.
+    let floor = config.quality.min_dimension.unwrap_or(0);
+    let width = floor.max(config.input_size.width);
+    let height = floor.max(config.input_size.height);
+    let mut decoder = JpegDecoder::new(Cursor::new(bytes))?;
+    decoder.scale(
+        u16::try_from(width).unwrap_or(u16::MAX),
+        u16::try_from(height).unwrap_or(u16::MAX),
+    )?;
+    Ok(DynamicImage::from_decoder(decoder)?)
+}
+
+// `to_rgb8` would simply drop the alpha, leaving whatever colour transparent
+// pixels happen to carry, so blend them over the background first.
+fn flatten_alpha(image: DynamicImage, background: Background) -> DynamicImage {
+    if !image.color().has_alpha() {
+        return image;
+    }
+    if is_16_bit(&image) {
+        return flatten_alpha_16(image, background);
+    }
+    let rgba = image.into_rgba8();
+    let rgb = RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, alpha] = rgba.get_pixel(x, y).0;
+        let alpha = u16::from(alpha);
+        let blend = |channel: u8, background: u8| {
+            ((u16::from(channel) * alpha + u16::from(background) * (255 - alpha) + 127) / 255) as u8
+        };
+        Rgb([
+            blend(r, background.0[0]),
+            blend(g, background.0[1]),
+            blend(b, background.0[2]),
+        ])
+    });
+    DynamicImage::ImageRgb8(rgb)
+}
+
+fn flatten_alpha_16(image: DynamicImage, background: Background) -> DynamicImage {
+    let rgba = image.into_rgba16();
+    let rgb = ImageBuffer::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, alpha] = rgba.get_pixel(x, y).0;
+        let alpha = u32::from(alpha);
+        let blend = |channel: u16, background: u8| {
+            let background = u32::from(background) * 257;
+            ((u32::from(channel) * alpha + background * (65535 - alpha) + 32767) / 65535) as u16
+        };
+        Rgb([
+            blend(r, background.0[0]),
+            blend(g, background.0[1]),
+            blend(b, background.0[2]),
+        ])
+    });
+    DynamicImage::ImageRgb16(rgb)
+}
+
+// Animations always yield their first frame. Only one further frame is decoded
+// to detect the drop, so a long animation costs no more than a short one.
+fn first_frame<'a>(
+    decoder: impl AnimationDecoder<'a>,
+    format: ImageFormat,
+) -> Result<DynamicImage, ImageError> {
+    let mut frames = decoder.into_frames();
+    let first = frames.next().ok_or_else(|| {
+        image::ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Exact(format),
+            "animation has no frames",
+        ))
+    })??;
+    if frames.next().is_some() {
+        warn!(?format, "multi-frame image, extra frames were dropped");
+    }
+    Ok(DynamicImage::ImageRgba8(first.into_buffer()))
+}
+
+fn image_to_tensor(image: &DynamicImage, config: &PreprocessConfig) -> Vec<f32> {
+    let resized = if config.center_crop {
+        resize_image(&center_crop(image), config)
+    } else {
+        resize_image(image, config)
+    };
+
+    match config.channels {
+        ChannelMode::Rgb => to_unit_tensor(&resized),
+        ChannelMode::Grayscale => to_luma_tensor(&resized),
+    }
+}
+
+fn is_16_bit(image: &DynamicImage) -> bool {
+    matches!(
+        image.color(),
+        ColorType::L16 | ColorType::La16 | ColorType::Rgb16 | ColorType::Rgba16
+    )
+}
+
+// Channel-first RGB in [0, 1]. 16-bit sources are scaled from their full range
+// instead of going through `to_rgb8`, which would truncate them to 8 bits.
+pub(crate) fn to_unit_tensor(image: &DynamicImage) -> Vec<f32> {
+    if !is_16_bit(image) {
+        return to_chw_tensor(&image.to_rgb8());
+    }
+    let rgb = image.to_rgb16();
+    (0..3)
+        .flat_map(|channel| {
+            rgb.pixels()
+                .map(move |pixel| f32::from(pixel[channel]) / 65535.0)
+        })
+        .collect()
+}
+
+// Computed from RGB rather than via `to_luma8`, which uses Rec. 709 weights and
+// rounds to u8.
+fn to_luma_tensor(image: &DynamicImage) -> Vec<f32> {
+    let luma = |[r, g, b]: [f32; 3], max: f32| (0.299 * r + 0.587 * g + 0.114 * b) / max;
+    if is_16_bit(image) {
+        let rgb = image.to_rgb16();
+        return rgb
+            .pixels()
+            .map(|pixel| luma(pixel.0.map(f32::from), 65535.0))
+            .collect();
+    }
+    let rgb = image.to_rgb8();
+    rgb.pixels()
+        .map(|pixel| luma(pixel.0.map(f32::from), 255.0))
+        .collect()
+}
+
+// Read from the header alone, without decoding any pixels.
+fn image_dimensions(bytes: &[u8]) -> Result<(u32, u32), ImageError> {
+    Ok(Reader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(image::ImageError::IoError)?
+        .into_dimensions()?)
+}
+
+// Worst-case RGBA decode buffer at the source bit depth plus the FP32 output
+// tensor, computed from the header alone so oversized images are refused
+// before any pixel allocation.
+pub fn estimate_memory(bytes: &[u8], size: InputSize) -> Result<u64, ImageError> {
+    let tensor_bytes =
+        3 * u64::from(size.width) * u64::from(size.height) * std::mem::size_of::<f32>() as u64;
+
+    let (width, height) = image_dimensions(bytes)?;
+    let decoded_bytes_per_pixel = 4 * channel_bytes(bytes)?;
+
+    Ok(u64::from(width) * u64::from(height) * decoded_bytes_per_pixel + tensor_bytes)
+}
+
+// Bytes per channel once decoded. Of the supported formats only PNG can carry
+// 16 bits per channel.
+fn channel_bytes(bytes: &[u8]) -> Result<u64, ImageError> {
+    if image::guess_format(bytes).ok() != Some(ImageFormat::Png) {
+        return Ok(1);
+    }
+    let color = PngDecoder::new(Cursor::new(bytes))?.color_type();
+    Ok(u64::from(color.bytes_per_pixel() / color.channel_count()).max(1))
 }
 
-fn resize_image(image: &DynamicImage) -> DynamicImage {
-    image.resize_exact(224, 224, FilterType::CatmullRom)
+fn check_end_marker(bytes: &[u8]) -> Result<(), ImageError> {
+    const JPEG_EOI: [u8; 2] = [0xFF, 0xD9];
+    const PNG_IEND: [u8; 8] = [0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82];
+
+    match image::guess_format(bytes) {
+        Ok(ImageFormat::Jpeg) => {
+            let end = bytes
+                .iter()
+                .rposition(|&byte| byte != 0)
+                .map_or(0, |i| i + 1);
+            if !bytes[..end].ends_with(&JPEG_EOI) {
+                return Err(ImageError::Truncated("JPEG EOI"));
+            }
+        }
+        Ok(ImageFormat::Png) => {
+            if !bytes.ends_with(&PNG_IEND) {
+                return Err(ImageError::Truncated("PNG IEND"));
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
 }
 
-fn to_chw_tensor(image: &RgbImage) -> Vec<f32> {
+// Upscaling a capture smaller than the model input in both dimensions only
+// invents detail, so such images are treated as unusable instead.
+fn check_no_upscale(image: &DynamicImage, size: InputSize) -> Result<(), QualityRejection> {
+    let (width, height) = image.dimensions();
+    if width < size.width && height < size.height {
+        return Err(QualityRejection::WouldUpscale {
+            width,
+            height,
+            target_width: size.width,
+            target_height: size.height,
+        });
+    }
+
+    Ok(())
+}
+
+pub fn center_crop(image: &DynamicImage) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    let side = width.min(height);
+    image.crop_imm((width - side) / 2, (height - side) / 2, side, side)
+}
+
+fn resize_image(image: &DynamicImage, config: &PreprocessConfig) -> DynamicImage {
+    let size = config.input_size;
+    let filter = config.resize_filter.filter_type();
+    let ResizeMode::Letterbox { fill } = config.resize else {
+        return image.resize_exact(size.width, size.height, filter);
+    };
+
+    let (width, height) = image.dimensions();
+    let scale = f64::min(
+        f64::from(size.width) / f64::from(width),
+        f64::from(size.height) / f64::from(height),
+    );
+    let scaled_width = ((f64::from(width) * scale).round() as u32).clamp(1, size.width);
+    let scaled_height = ((f64::from(height) * scale).round() as u32).clamp(1, size.height);
+    let scaled = image.resize_exact(scaled_width, scaled_height, filter);
+    let x = i64::from((size.width - scaled_width) / 2);
+    let y = i64::from((size.height - scaled_height) / 2);
+
+    if is_16_bit(image) {
+        let fill = Rgb(fill.map(|value| u16::from(value) * 257));
+        let mut canvas = ImageBuffer::from_pixel(size.width, size.height, fill);
+        imageops::overlay(&mut canvas, &scaled.to_rgb16(), x, y);
+        return DynamicImage::ImageRgb16(canvas);
+    }
+    let mut canvas = RgbImage::from_pixel(size.width, size.height, Rgb(fill));
+    imageops::overlay(&mut canvas, &scaled.to_rgb8(), x, y);
+    DynamicImage::ImageRgb8(canvas)
+}
+
+#[cfg(feature = "simd")]
+pub(crate) fn to_chw_tensor(image: &RgbImage) -> Vec<f32> {
+    to_chw_tensor_chunked(image)
+}
+
+#[cfg(not(feature = "simd"))]
+pub(crate) fn to_chw_tensor(image: &RgbImage) -> Vec<f32> {
+    to_chw_tensor_scalar(image)
+}
+
+pub fn to_chw_tensor_scalar(image: &RgbImage) -> Vec<f32> {
     let mut tensor = Vec::with_capacity((image.width() * image.height() * 3) as usize);
 
     for channel in 0..3 {
@@ -44,3 +954,25 @@ fn to_chw_tensor(image: &RgbImage) -> Vec<f32> {
 
     tensor
 }
+
+// Walks the interleaved buffer once and writes all three planes, reading each
+// value from a table of `v / 255.0` so the output is bit-identical to the
+// scalar path. The straight-line inner loop is what lets the compiler vectorize.
+#[cfg(feature = "simd")]
+pub fn to_chw_tensor_chunked(image: &RgbImage) -> Vec<f32> {
+    static NORMALIZED: std::sync::OnceLock<[f32; 256]> = std::sync::OnceLock::new();
+    let table = NORMALIZED.get_or_init(|| std::array::from_fn(|value| value as f32 / 255.0));
+
+    let plane = (image.width() * image.height()) as usize;
+    let mut tensor = vec![0.0; plane * 3];
+    let (red, rest) = tensor.split_at_mut(plane);
+    let (green, blue) = rest.split_at_mut(plane);
+
+    for (((pixel, r), g), b) in image.as_raw().chunks_exact(3).zip(red).zip(green).zip(blue) {
+        *r = table[pixel[0] as usize];
+        *g = table[pixel[1] as usize];
+        *b = table[pixel[2] as usize];
+    }
+
+    tensor
+}
@@ -0,0 +1,56 @@
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::ranking;
+
+#[derive(Debug, Error)]
+pub enum LabelsError {
+    #[error("failed to read labels: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("labels file has no labels")]
+    Empty,
+}
+
+// Class names by output index, one per line as in Triton's label files. A
+// blank line keeps its index but leaves that class unlabelled.
+#[derive(Debug, Clone, Default)]
+pub struct Labels {
+    names: Vec<String>,
+}
+
+impl Labels {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LabelsError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, LabelsError> {
+        let names: Vec<String> = text.lines().map(|line| line.trim().to_string()).collect();
+        if names.iter().all(String::is_empty) {
+            return Err(LabelsError::Empty);
+        }
+        Ok(Self { names })
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.names
+            .get(index)
+            .map(String::as_str)
+            .filter(|name| !name.is_empty())
+    }
+
+    // None when there are no scores or the best index has no label, e.g. a
+    // model with more outputs than the file has lines.
+    pub fn predicted(&self, scores: &[f32]) -> Option<&str> {
+        let (index, _) = ranking::top_k(scores, 1).into_iter().next()?;
+        self.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
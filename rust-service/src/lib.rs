@@ -1,8 +1,39 @@
+pub mod activation;
+pub mod audit;
+pub mod batch;
+pub mod deadline;
+pub mod decision;
+pub mod dry_run;
+pub mod embedding;
+pub mod endpoints;
+pub mod errors;
+pub mod face;
+pub mod fetch;
+pub mod fingerprint;
+pub mod health;
 pub mod image;
+pub mod labels;
+pub mod limiter;
+pub mod liveness;
+pub mod metrics;
+pub mod model_spec;
+pub mod outcome;
+pub mod pipeline;
+pub mod quality;
+pub mod ranking;
+pub mod sampling;
+pub mod score_cache;
+pub mod shutdown;
+#[cfg(feature = "otel")]
+pub mod telemetry;
+pub mod timing;
 pub mod triton_client;
+pub mod usage;
 
 pub use image::ImageTensor;
 
 pub mod verify {
     tonic::include_proto!("verify");
+
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("verify_descriptor");
 }
@@ -0,0 +1,173 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tonic::{metadata::MetadataValue, Status};
+
+pub const RETRY_AFTER_HEADER: &str = "retry-after-ms";
+
+const DEFAULT_LATENCY: Duration = Duration::from_millis(100);
+const MIN_RETRY_AFTER: Duration = Duration::from_millis(50);
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+pub struct ConcurrencyLimiter {
+    semaphore: Semaphore,
+    capacity: usize,
+    avg_latency_micros: AtomicU64,
+}
+
+pub struct LimiterPermit<'a> {
+    _permit: SemaphorePermit<'a>,
+    limiter: &'a ConcurrencyLimiter,
+    started: Instant,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            semaphore: Semaphore::new(capacity),
+            capacity,
+            avg_latency_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub fn try_acquire(&self) -> Result<LimiterPermit<'_>, Duration> {
+        match self.semaphore.try_acquire() {
+            Ok(permit) => Ok(LimiterPermit {
+                _permit: permit,
+                limiter: self,
+                started: Instant::now(),
+            }),
+            Err(_) => Err(self.retry_after()),
+        }
+    }
+
+    // With every slot busy, a slot frees up roughly every avg_latency / capacity.
+    pub fn retry_after(&self) -> Duration {
+        let avg = match self.avg_latency_micros.load(Ordering::Relaxed) {
+            0 => DEFAULT_LATENCY,
+            micros => Duration::from_micros(micros),
+        };
+        let in_flight = self.capacity - self.semaphore.available_permits();
+        let hint = avg.mul_f64(in_flight.max(1) as f64 / self.capacity.max(1) as f64);
+
+        hint.clamp(MIN_RETRY_AFTER, MAX_RETRY_AFTER)
+    }
+
+    fn record_latency(&self, elapsed: Duration) {
+        let sample = elapsed.as_micros() as u64;
+        // Exponentially weighted moving average with alpha = 1/8; a lost
+        // update under contention only skews the hint slightly.
+        let previous = self.avg_latency_micros.load(Ordering::Relaxed);
+        let updated = if previous == 0 {
+            sample
+        } else {
+            previous - previous / 8 + sample / 8
+        };
+        self.avg_latency_micros.store(updated, Ordering::Relaxed);
+    }
+}
+
+impl Drop for LimiterPermit<'_> {
+    fn drop(&mut self) {
+        self.limiter.record_latency(self.started.elapsed());
+    }
+}
+
+// Per-user token buckets refilling at `rate` tokens a second up to `burst`.
+// A bucket left idle until full is indistinguishable from a fresh one, so
+// those are swept out instead of accumulating one entry per user ever seen.
+pub struct UserRateLimiter {
+    rate: f64,
+    burst: f64,
+    state: Mutex<RateState>,
+}
+
+struct RateState {
+    buckets: HashMap<String, TokenBucket>,
+    last_sweep: Instant,
+}
+
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl UserRateLimiter {
+    pub fn new(rate: f64, burst: u32) -> Result<Self, String> {
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(format!("rate limit must be positive, got {rate}"));
+        }
+        if burst == 0 {
+            return Err("rate limit burst must be at least 1".into());
+        }
+        Ok(Self {
+            rate,
+            burst: f64::from(burst),
+            state: Mutex::new(RateState {
+                buckets: HashMap::new(),
+                last_sweep: Instant::now(),
+            }),
+        })
+    }
+
+    pub fn check(&self, user_id: &str) -> Result<(), Duration> {
+        self.check_at(user_id, Instant::now())
+    }
+
+    // Takes a token for `user_id`, or reports how long until one is available.
+    pub fn check_at(&self, user_id: &str, now: Instant) -> Result<(), Duration> {
+        let refill_time = Duration::from_secs_f64(self.burst / self.rate);
+        let mut state = self.state.lock().expect("rate limiter lock poisoned");
+        if now.saturating_duration_since(state.last_sweep) >= refill_time {
+            state
+                .buckets
+                .retain(|_, bucket| now.saturating_duration_since(bucket.updated) < refill_time);
+            state.last_sweep = now;
+        }
+
+        let bucket = state
+            .buckets
+            .entry(user_id.to_string())
+            .or_insert(TokenBucket {
+                tokens: self.burst,
+                updated: now,
+            });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate);
+            Err(wait.clamp(MIN_RETRY_AFTER, MAX_RETRY_AFTER))
+        }
+    }
+
+    pub fn tracked_users(&self) -> usize {
+        self.state
+            .lock()
+            .expect("rate limiter lock poisoned")
+            .buckets
+            .len()
+    }
+}
+
+pub fn resource_exhausted(message: impl Into<String>, retry_after: Duration) -> Status {
+    let mut status = Status::resource_exhausted(message);
+    if let Ok(value) = retry_after
+        .as_millis()
+        .to_string()
+        .parse::<MetadataValue<_>>()
+    {
+        status.metadata_mut().insert(RETRY_AFTER_HEADER, value);
+    }
+    status
+}
@@ -0,0 +1,46 @@
+use crate::{
+    decision::{Decision, VerifyThreshold},
+    triton_client::{TritonClient, TritonError},
+    ImageTensor,
+};
+
+// Anti-spoofing model scored before identity verification so screen replays
+// are turned away without running the identity model. Its first output value
+// is read as the probability that the capture is live.
+#[derive(Clone)]
+pub struct LivenessCheck {
+    triton: TritonClient,
+    threshold: VerifyThreshold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Liveness {
+    pub score: f32,
+    pub live: bool,
+}
+
+impl LivenessCheck {
+    pub fn new(triton: TritonClient, threshold: VerifyThreshold) -> Self {
+        Self { triton, threshold }
+    }
+
+    pub async fn check(
+        &self,
+        tensor: &ImageTensor,
+        request_id: &str,
+    ) -> Result<Liveness, TritonError> {
+        let score = self
+            .triton
+            .infer_with_id(tensor, request_id)
+            .await?
+            .first()
+            .copied()
+            .ok_or_else(|| {
+                TritonError::InvalidResponse("liveness model returned no scores".into())
+            })?;
+        Ok(Liveness {
+            score,
+            live: self.threshold.decide(score) == Decision::Accept,
+        })
+    }
+}
@@ -1,15 +1,710 @@
-use std::net::SocketAddr;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    net::SocketAddr,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use tonic::{transport::Server, Request, Response, Status};
-use tracing::{error, info};
+use prost::Message;
+use tokio::net::TcpSocket;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+#[cfg(unix)]
+use tokio_stream::wrappers::UnixListenerStream;
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+use tonic::{
+    transport::{server::Router, Server},
+    Code, Request, Response, Status,
+};
+use tonic_health::server::HealthReporter;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+use tracing_subscriber::{prelude::*, EnvFilter};
+use uuid::Uuid;
 
-use rust_service::{image, triton_client::TritonClient, verify};
+#[cfg(feature = "otel")]
+use rust_service::telemetry;
+use rust_service::{
+    activation::Activation,
+    audit::{AuditRecord, AuditSigner, AuditSink, JsonLinesAuditSink},
+    batch, deadline,
+    decision::{Decision, DecisionTable, VerifyThreshold},
+    dry_run,
+    embedding::{self, Projection},
+    errors::{ErrorVerbosity, FieldViolations},
+    face::{FaceDetector, FaceError},
+    fetch::{Fetched, ImageFetcher},
+    fingerprint::{config_fingerprint, model_fingerprint, template_fingerprint},
+    health,
+    image::{self, CropRegion, ImageError, ImageTensor, InputSize, PreprocessConfig, Rotation},
+    labels::Labels,
+    limiter::{self, ConcurrencyLimiter, LimiterPermit, UserRateLimiter},
+    liveness::LivenessCheck,
+    metrics::{self, Metrics},
+    model_spec::ModelSpecs,
+    outcome::VerificationOutcome,
+    pipeline::PreprocessPipeline,
+    quality::{self, ImageQuality, QualityGates, QualityRejection},
+    ranking,
+    sampling::LogSampler,
+    score_cache::ScoreCache,
+    shutdown,
+    timing::ServerTiming,
+    triton_client::{
+        BatchUtilization, InputDtype, Keepalive, ModelAllowlist, TritonClient, TritonError,
+    },
+    usage::{self, ResourceUsage},
+    verify,
+};
 
 use verify::image_processor_server::{ImageProcessor, ImageProcessorServer};
-use verify::{VerifyRequest, VerifyResponse};
+use verify::model_admin_server::{ModelAdmin, ModelAdminServer};
+use verify::{
+    ClassScore, CompareRequest, CompareResponse, EnrollRequest, ModelAdminRequest,
+    ModelAdminResponse, Template, VerificationDecision, VerificationStatus, VerifyImagesRequest,
+    VerifyImagesResponse, VerifyRequest, VerifyResponse,
+};
+
+// Caller-supplied IDs end up in every log line and Triton request.
+const MAX_REQUEST_ID_LEN: usize = 128;
+// Classifier outputs are small; this only bounds response size.
+const MAX_TOP_K: u32 = 100;
 
 struct ImageProcessorService {
     triton: TritonClient,
+    preprocess: Arc<PreprocessConfig>,
+    preprocess_timeout: Option<Duration>,
+    config_fingerprint: String,
+    template_fingerprint: String,
+    decision_table: Option<DecisionTable>,
+    threshold: VerifyThreshold,
+    liveness: Option<LivenessCheck>,
+    face_detector: Option<Arc<FaceDetector>>,
+    models: ModelAllowlist,
+    crop_regions: Arc<Vec<CropRegion>>,
+    activation: Activation,
+    labels: Option<Arc<Labels>>,
+    limiter: Option<ConcurrencyLimiter>,
+    rate_limiter: Option<UserRateLimiter>,
+    errors: ErrorVerbosity,
+    log_sampler: LogSampler,
+    audit_signer: Option<AuditSigner>,
+    audit_sink: Option<Arc<dyn AuditSink>>,
+    fetcher: ImageFetcher<Preprocessed>,
+    projection: Option<Projection>,
+    drift_threshold: Option<f32>,
+    metrics: Arc<Metrics>,
+    max_image_bytes: usize,
+    score_cache: Option<ScoreCache>,
+    batch_max_images: usize,
+    batch_concurrency: usize,
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PreprocessOptions {
+    score_regions: bool,
+    rotation: Rotation,
+    assess_quality: bool,
+}
+
+struct Preprocessed {
+    tensor: ImageTensor,
+    quality: Option<ImageQuality>,
+}
+
+impl ImageProcessorService {
+    // Decoding is CPU-bound and attacker-controlled, so it runs on the blocking
+    // pool under a wall-clock limit. A timed-out task keeps running to
+    // completion in the background, but the request and its worker are freed.
+    async fn run_preprocess(
+        &self,
+        image_data: Vec<u8>,
+        request_id: &str,
+        options: PreprocessOptions,
+        timing: &mut ServerTiming,
+        usage: &mut ResourceUsage,
+    ) -> Result<Result<Preprocessed, ImageError>, Status> {
+        usage.bytes_in += image_data.len() as u64;
+        let PreprocessOptions {
+            score_regions,
+            rotation,
+            assess_quality,
+        } = options;
+        let preprocess = if rotation == Rotation::None {
+            Arc::clone(&self.preprocess)
+        } else {
+            Arc::new(PreprocessConfig {
+                rotation,
+                ..(*self.preprocess).clone()
+            })
+        };
+        let regions = Arc::clone(&self.crop_regions);
+        // Crop regions are fractions of the whole scan, so they skip detection.
+        let detector = self
+            .face_detector
+            .clone()
+            .filter(|_| !score_regions && !self.dry_run);
+        let request_id = request_id.to_string();
+        let runtime = tokio::runtime::Handle::current();
+        let task = tokio::task::spawn_blocking(move || {
+            let cpu_started = usage::thread_cpu_time();
+            let started = Instant::now();
+            let decoded = image::decode(&image_data, &preprocess);
+            let decode_time = started.elapsed();
+
+            let preprocessed = decoded.and_then(|img| {
+                // Blocking this worker on the detector keeps the call under the
+                // same preprocess timeout as decoding.
+                let img = match &detector {
+                    Some(detector) => runtime.block_on(detector.crop(&img, &request_id))?,
+                    None => img,
+                };
+                let tensor = if score_regions {
+                    image::tensor_from_regions(&img, &regions, &preprocess)?
+                } else if let Some(pipeline) = &preprocess.pipeline {
+                    pipeline.run(&img)?
+                } else {
+                    image::tensor_from_image(&img, &preprocess)
+                };
+                Ok(Preprocessed {
+                    tensor,
+                    quality: assess_quality.then(|| quality::assess(&img)),
+                })
+            });
+            let cpu_time = cpu_started
+                .zip(usage::thread_cpu_time())
+                .map(|(start, end)| end.saturating_sub(start))
+                .unwrap_or_default();
+            (
+                preprocessed,
+                decode_time,
+                started.elapsed() - decode_time,
+                cpu_time,
+            )
+        });
+
+        let joined = match self.preprocess_timeout {
+            Some(limit) => tokio::time::timeout(limit, task).await.map_err(|_| {
+                self.errors.reject(
+                    Code::DeadlineExceeded,
+                    format!("image decoding exceeded {}ms", limit.as_millis()),
+                )
+            })?,
+            None => task.await,
+        };
+
+        let (preprocessed, decode_time, preprocess_time, cpu_time) = joined.map_err(|err| {
+            self.errors
+                .fail(Code::Internal, "image preprocessing task failed", err)
+        })?;
+        timing.record("decode", decode_time);
+        timing.record("preprocess", preprocess_time);
+        usage.cpu_time += cpu_time;
+
+        Ok(preprocessed)
+    }
+
+    // Revalidates a previously preprocessed copy with the origin before
+    // downloading and decoding the image again.
+    async fn preprocess_uri(
+        &self,
+        uri: &str,
+        request_id: &str,
+        options: PreprocessOptions,
+        timing: &mut ServerTiming,
+        usage: &mut ResourceUsage,
+    ) -> Result<Result<Arc<Preprocessed>, ImageError>, Status> {
+        let variant = format!("{options:?}");
+        let started = Instant::now();
+        let fetched =
+            self.fetcher
+                .fetch(uri, &variant)
+                .await
+                .map_err(|err| match err.status_code() {
+                    Code::InvalidArgument => {
+                        self.errors.reject(Code::InvalidArgument, err.to_string())
+                    }
+                    Code::NotFound => self.errors.reject(Code::NotFound, "image file not found"),
+                    code => self.errors.fail(code, "failed to fetch image", err),
+                })?;
+        timing.record("fetch", started.elapsed());
+
+        match fetched {
+            Fetched::Cached(preprocessed) => Ok(Ok(preprocessed)),
+            Fetched::Fresh { bytes, validators } => {
+                let preprocessed = self
+                    .run_preprocess(bytes, request_id, options, timing, usage)
+                    .await?
+                    .map(Arc::new);
+                if let Ok(preprocessed) = &preprocessed {
+                    self.fetcher
+                        .store(uri, &variant, validators, Arc::clone(preprocessed));
+                }
+                Ok(preprocessed)
+            }
+        }
+    }
+
+    fn acquire_permit(&self) -> Result<Option<LimiterPermit<'_>>, Status> {
+        match &self.limiter {
+            Some(limiter) => limiter.try_acquire().map(Some).map_err(|retry_after| {
+                limiter::resource_exhausted("too many concurrent verifications", retry_after)
+            }),
+            None => Ok(None),
+        }
+    }
+
+    // A request's model and model_version override the configured ones; the
+    // other model runs at Triton's latest version.
+    fn fingerprint(&self, request: &VerifyRequest) -> String {
+        let (model, version) = match (request.model.as_str(), request.model_version.as_str()) {
+            ("", "") => return self.config_fingerprint.clone(),
+            ("", version) => (self.triton.model_name(), version),
+            (model, _) => (model, ""),
+        };
+        model_fingerprint(
+            &self.triton,
+            &self.preprocess,
+            self.activation,
+            self.face_detector.as_deref(),
+            model,
+            version,
+        )
+    }
+
+    fn image_error(&self, err: ImageError) -> Status {
+        match err {
+            ImageError::LowQuality(reason) => {
+                self.errors
+                    .fail(Code::InvalidArgument, quality_message(&reason), reason)
+            }
+            ImageError::MemoryBudgetExceeded { .. } => self.errors.fail(
+                Code::ResourceExhausted,
+                "image is too large to process",
+                err,
+            ),
+            ImageError::Truncated(_) | ImageError::FormatNotAllowed(_) => {
+                self.errors.reject(Code::InvalidArgument, err.to_string())
+            }
+            ImageError::Face(FaceError::NotDetected) => self.errors.fail(
+                Code::InvalidArgument,
+                "No face detected, please retake the photo",
+                err,
+            ),
+            ImageError::Face(FaceError::Detection(err)) => {
+                self.errors
+                    .fail_with(err.error_code(), "face detection failed", err)
+            }
+            ImageError::EmptyRegion(_) => self.errors.fail(
+                Code::InvalidArgument,
+                "image does not contain the configured regions",
+                err,
+            ),
+            _ => self
+                .errors
+                .fail(Code::Internal, "image preprocessing failed", err),
+        }
+    }
+
+    // `deadline` is when the caller stops waiting, worked out once when the
+    // call arrived rather than from its grpc-timeout at this point.
+    async fn verify_until(
+        &self,
+        request: Request<VerifyRequest>,
+        deadline: Option<Instant>,
+    ) -> Result<Response<VerifyResponse>, Status> {
+        let request_id = match request.get_ref().request_id.as_str() {
+            "" => Uuid::new_v4().to_string(),
+            id => id.to_string(),
+        };
+        let span = info_span!("verify", %request_id);
+        #[cfg(feature = "otel")]
+        telemetry::set_parent(&span, request.metadata());
+        let result = self
+            .verify(request, request_id, deadline)
+            .instrument(span)
+            .await;
+        self.metrics
+            .record_verification(matches!(&result, Ok(response) if response.get_ref().success));
+        result
+    }
+
+    async fn verify(
+        &self,
+        request: Request<VerifyRequest>,
+        request_id: String,
+        deadline: Option<Instant>,
+    ) -> Result<Response<VerifyResponse>, Status> {
+        let _permit = self.acquire_permit()?;
+
+        let tenant = usage::tenant(&request);
+        let mut request = request.into_inner();
+        // Caller-chosen IDs need not be UUIDs; those are sampled at random.
+        let request_uuid = Uuid::parse_str(&request_id).unwrap_or_else(|_| Uuid::new_v4());
+        let sampled = self.log_sampler.should_sample(&request_uuid);
+        let mut violations = FieldViolations::default();
+        if request.top_k > MAX_TOP_K {
+            violations.add("top_k", format!("top_k must be at most {MAX_TOP_K}"));
+        }
+        if request_id.len() > MAX_REQUEST_ID_LEN {
+            violations.add(
+                "request_id",
+                format!("request_id exceeds {MAX_REQUEST_ID_LEN} characters"),
+            );
+        }
+        match (request.image_data.is_empty(), request.image_uri.is_empty()) {
+            (true, true) => violations.add("image_data", "image data cannot be empty"),
+            (false, false) => violations.add(
+                "image_uri",
+                "image_uri cannot be combined with inline image_data",
+            ),
+            _ => {}
+        }
+        violations.check_size("image_data", request.image_data.len(), self.max_image_bytes);
+        if request.user_id.is_empty() {
+            violations.add("user_id", "user_id is required");
+        }
+        if let Some(table) = &self.decision_table {
+            if !request.segment.is_empty() && !table.has_segment(&request.segment) {
+                violations.add("segment", format!("unknown segment '{}'", request.segment));
+            }
+        }
+        let rotation = Rotation::try_from(request.rotate_degrees).unwrap_or_else(|message| {
+            violations.add("rotate_degrees", message);
+            Rotation::None
+        });
+        if !request.model.is_empty() {
+            if let Err(message) = self.models.check(&request.model) {
+                violations.add("model", message);
+            }
+            if !request.model_version.is_empty() {
+                violations.add(
+                    "model_version",
+                    "model_version cannot be combined with model",
+                );
+            }
+        }
+        violations.into_result()?;
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter
+                .check(&request.user_id)
+                .map_err(|retry_after| {
+                    limiter::resource_exhausted("verification rate limit exceeded", retry_after)
+                })?;
+        }
+        if request.score_regions && self.crop_regions.is_empty() {
+            return Err(self
+                .errors
+                .reject(Code::FailedPrecondition, "no crop regions are configured"));
+        }
+
+        let config_fingerprint = self.fingerprint(&request);
+        let mut timing = ServerTiming::default();
+        let mut usage = ResourceUsage::default();
+        let options = PreprocessOptions {
+            score_regions: request.score_regions,
+            rotation,
+            assess_quality: request.include_image_quality,
+        };
+        // URI requests are left to the fetcher's own cache.
+        let cache_key = self
+            .score_cache
+            .as_ref()
+            .filter(|_| request.image_uri.is_empty())
+            .map(|_| {
+                let variant = format!(
+                    "{rotation:?}|{}|{}|{}",
+                    request.score_regions, request.model, request.model_version
+                );
+                ScoreCache::key(&request.image_data, &variant)
+            });
+        let preprocessed = if request.image_uri.is_empty() {
+            let image_data = std::mem::take(&mut request.image_data);
+            self.run_preprocess(image_data, &request_id, options, &mut timing, &mut usage)
+                .instrument(info_span!("preprocess"))
+                .await?
+                .map(Arc::new)
+        } else {
+            self.preprocess_uri(
+                &request.image_uri,
+                &request_id,
+                options,
+                &mut timing,
+                &mut usage,
+            )
+            .instrument(info_span!("preprocess", uri = %request.image_uri))
+            .await?
+        };
+        // A photo that can't be used is answered with a retake status rather
+        // than an error, which clients would read as a malformed request.
+        let retake = match preprocessed {
+            Ok(preprocessed) => Ok(preprocessed),
+            Err(ImageError::LowQuality(reason)) => {
+                info!(%reason, "image rejected by quality gates");
+                let status = match reason {
+                    QualityRejection::Blurry { .. } => VerificationStatus::ImageTooBlurry,
+                    _ => VerificationStatus::NoUsableFace,
+                };
+                Err((status, quality_message(&reason)))
+            }
+            Err(ImageError::Face(FaceError::NotDetected)) => {
+                info!("image rejected, no face detected");
+                Err((
+                    VerificationStatus::NoUsableFace,
+                    "No face detected, please retake the photo",
+                ))
+            }
+            Err(err) => return Err(self.image_error(err)),
+        };
+        let preprocessed = match retake {
+            Ok(preprocessed) => preprocessed,
+            Err((status, message)) => {
+                let mut response = Response::new(VerifyResponse {
+                    success: false,
+                    score: 0.0,
+                    message: message.to_string(),
+                    status: status as i32,
+                    config_fingerprint: config_fingerprint.clone(),
+                    ..Default::default()
+                });
+                usage.bytes_out = response.get_ref().encoded_len() as u64;
+                usage.emit(&tenant, "ProcessImage");
+                timing.attach(&mut response);
+                return Ok(response);
+            }
+        };
+
+        let mut liveness_score = None;
+        if let Some(liveness) = self.liveness.as_ref().filter(|_| !self.dry_run) {
+            let started = Instant::now();
+            let liveness = liveness
+                .check(&preprocessed.tensor, &request_id)
+                .await
+                .map_err(|err| {
+                    self.errors
+                        .fail_with(err.error_code(), "liveness check failed", err)
+                })?;
+            timing.record("liveness", started.elapsed());
+            if !liveness.live {
+                info!(score = liveness.score, "image rejected by liveness check");
+                let mut response = Response::new(VerifyResponse {
+                    success: false,
+                    score: 0.0,
+                    message: "Liveness check failed, please retake the photo with a live camera"
+                        .to_string(),
+                    status: VerificationStatus::SpoofDetected as i32,
+                    config_fingerprint: config_fingerprint.clone(),
+                    liveness_score: Some(liveness.score),
+                    ..Default::default()
+                });
+                usage.bytes_out = response.get_ref().encoded_len() as u64;
+                usage.emit(&tenant, "ProcessImage");
+                timing.attach(&mut response);
+                return Ok(response);
+            }
+            liveness_score = Some(liveness.score);
+        }
+
+        let started = Instant::now();
+        let model_version =
+            (!request.model_version.is_empty()).then_some(request.model_version.as_str());
+        let cache = self.score_cache.as_ref().zip(cache_key);
+        let cached = cache.and_then(|(cache, key)| cache.get(&key));
+        let logits = match cached {
+            _ if self.dry_run => dry_run::scores(&preprocessed.tensor, self.preprocess.layout),
+            Some(scores) => scores.to_vec(),
+            None => {
+                let triton = match deadline {
+                    Some(deadline) => Cow::Owned(self.triton.clone().with_deadline(deadline)),
+                    None => Cow::Borrowed(&self.triton),
+                };
+                let logits = if request.model.is_empty() {
+                    triton
+                        .infer_with_version(&preprocessed.tensor, &request_id, model_version)
+                        .instrument(info_span!("inference", model = self.triton.model_name()))
+                        .await
+                } else {
+                    triton
+                        .infer_with_model(&request.model, &preprocessed.tensor, &request_id)
+                        .instrument(info_span!("inference", model = %request.model))
+                        .await
+                }
+                .map_err(|err| {
+                    self.errors
+                        .fail_with(err.error_code(), "inference failed", err)
+                })?;
+                if let Some((cache, key)) = cache {
+                    cache.insert(key, logits.clone());
+                }
+                logits
+            }
+        };
+        timing.record("inference", started.elapsed());
+
+        let started = Instant::now();
+        let mut region_scores = HashMap::new();
+        let score = if request.score_regions {
+            let per_region = logits.len() / self.crop_regions.len();
+            if per_region == 0 {
+                return Err(self.errors.fail(
+                    Code::Internal,
+                    "inference failed",
+                    "triton returned fewer scores than crop regions",
+                ));
+            }
+            for (region, chunk) in self.crop_regions.iter().zip(logits.chunks(per_region)) {
+                let region_score = self.activation.apply(chunk)[0];
+                // min() skips NaN, so one bad region could otherwise pass.
+                if !region_score.is_finite() {
+                    return Err(self.errors.fail(
+                        Code::Internal,
+                        "inference failed",
+                        format!(
+                            "triton returned a non-finite score for region '{}'",
+                            region.name
+                        ),
+                    ));
+                }
+                region_scores.insert(region.name.clone(), region_score);
+            }
+            // Every region has to match, so the weakest one decides.
+            region_scores
+                .values()
+                .copied()
+                .fold(f32::INFINITY, f32::min)
+        } else {
+            self.activation
+                .apply(&logits)
+                .first()
+                .copied()
+                .unwrap_or_default()
+        };
+        if !request.enrolled_embedding.is_empty()
+            && request.enrolled_embedding.len() != logits.len()
+        {
+            return Err(self.errors.reject(
+                Code::InvalidArgument,
+                format!(
+                    "enrolled_embedding has {} dimensions but the model produced {}",
+                    request.enrolled_embedding.len(),
+                    logits.len()
+                ),
+            ));
+        }
+        let drift = embedding::drift(&logits, &request.enrolled_embedding);
+        let drift_exceeded = matches!(
+            (drift, self.drift_threshold),
+            (Some(drift), Some(threshold)) if drift > threshold
+        );
+        let decision = match &self.decision_table {
+            Some(table) => table
+                .decide(&request.segment, score)
+                .unwrap_or(Decision::Reject),
+            None => self.threshold.decide(score),
+        };
+        let success = decision == Decision::Accept;
+        if sampled {
+            let outcome = VerificationOutcome {
+                request_id: request_id.to_string(),
+                score,
+                decision,
+                embedding: logits.clone(),
+                timings: VerificationOutcome::stage_timings(&timing),
+                model_fingerprint: config_fingerprint.clone(),
+            };
+            debug!(
+                %request_id,
+                shape = ?preprocessed.tensor.shape,
+                ?region_scores,
+                outcome = %serde_json::to_string(&outcome).unwrap_or_default(),
+                "verification detail"
+            );
+        }
+        info!(%request_id, success, score, "verification completed");
+        let audit = match &self.audit_signer {
+            Some(signer) => {
+                let record = signer.sign(AuditRecord::new(
+                    request_id.as_str(),
+                    &request.user_id,
+                    config_fingerprint.as_str(),
+                    score,
+                    decision,
+                    unix_millis(),
+                ));
+                if let Some(sink) = &self.audit_sink {
+                    // A decision that cannot be audited is not handed out.
+                    sink.persist(&record).map_err(|err| {
+                        self.errors
+                            .fail(Code::Internal, "failed to record audit entry", err)
+                    })?;
+                }
+                Some(record.into())
+            }
+            None => None,
+        };
+        let top_classes = ranking::top_k(&self.activation.apply(&logits), request.top_k as usize)
+            .into_iter()
+            .map(|(index, score)| ClassScore {
+                index: index as u32,
+                score,
+            })
+            .collect();
+        // Argmax is unaffected by the activation, so the raw logits will do.
+        let predicted_label = match &self.labels {
+            Some(labels) => labels.predicted(&logits).unwrap_or_else(|| {
+                debug!(classes = logits.len(), "predicted class has no label");
+                ""
+            }),
+            None => "",
+        }
+        .to_string();
+        let message = match decision {
+            Decision::Accept => "Verification succeeded",
+            Decision::Review => "Verification requires manual review",
+            Decision::Reject => "Verification failed",
+        };
+        let response = VerifyResponse {
+            success,
+            score,
+            message: if self.dry_run {
+                format!("{message} (dry run, Triton was not called)")
+            } else {
+                message.to_string()
+            },
+            status: if success {
+                VerificationStatus::Verified as i32
+            } else {
+                VerificationStatus::NotVerified as i32
+            },
+            config_fingerprint,
+            decision: VerificationDecision::from(decision) as i32,
+            region_scores,
+            raw_logits: if request.include_logits {
+                logits
+            } else {
+                Vec::new()
+            },
+            audit,
+            image_quality: preprocessed.quality.map(Into::into),
+            drift,
+            drift_exceeded,
+            liveness_score,
+            top_classes,
+            predicted_label,
+        };
+        timing.record("postprocess", started.elapsed());
+
+        usage.bytes_out = response.encoded_len() as u64;
+        usage.emit(&tenant, "ProcessImage");
+        self.metrics.observe_stages(&timing);
+
+        let mut response = Response::new(response);
+        timing.attach(&mut response);
+        Ok(response)
+    }
 }
 
 #[tonic::async_trait]
@@ -18,49 +713,293 @@ impl ImageProcessor for ImageProcessorService {
         &self,
         request: Request<VerifyRequest>,
     ) -> Result<Response<VerifyResponse>, Status> {
+        let deadline =
+            deadline::from_metadata(request.metadata()).map(|timeout| Instant::now() + timeout);
+        self.verify_until(request, deadline).await
+    }
+
+    async fn enroll(&self, request: Request<EnrollRequest>) -> Result<Response<Template>, Status> {
+        let _permit = self.acquire_permit()?;
+
+        let tenant = usage::tenant(&request);
         let request = request.into_inner();
+        let request_id = Uuid::new_v4().to_string();
+        let mut violations = FieldViolations::default();
         if request.image_data.is_empty() {
-            return Err(Status::invalid_argument("image data cannot be empty"));
+            violations.add("image_data", "image data cannot be empty");
         }
+        violations.check_size("image_data", request.image_data.len(), self.max_image_bytes);
         if request.user_id.is_empty() {
-            return Err(Status::invalid_argument("user_id is required"));
+            violations.add("user_id", "user_id is required");
+        }
+        violations.into_result()?;
+        if request.reduce_dimensions && self.projection.is_none() {
+            return Err(self.errors.reject(
+                Code::FailedPrecondition,
+                "no embedding projection is configured",
+            ));
         }
 
-        let tensor = image::preprocess(&request.image_data)
-            .map_err(|err| Status::internal(format!("image preprocessing failed: {err}")))?;
+        let mut timing = ServerTiming::default();
+        let mut usage = ResourceUsage::default();
+        let preprocessed = self
+            .run_preprocess(
+                request.image_data,
+                &request_id,
+                PreprocessOptions::default(),
+                &mut timing,
+                &mut usage,
+            )
+            .await?
+            .map_err(|err| self.image_error(err))?;
 
-        let scores = self
+        let started = Instant::now();
+        let raw = self
             .triton
-            .infer(&tensor)
+            .infer_with_id(&preprocessed.tensor, &request_id)
             .await
-            .map_err(|err| Status::internal(format!("triton inference failed: {err}")))?;
+            .map_err(|err| {
+                self.errors
+                    .fail_with(err.error_code(), "inference failed", err)
+            })?;
+        timing.record("inference", started.elapsed());
 
-        let score = scores.first().copied().unwrap_or_default();
-        let success = score >= 0.5;
-        let response = VerifyResponse {
-            success,
-            score,
-            message: if success {
-                "Verification succeeded".to_string()
-            } else {
-                "Verification failed".to_string()
-            },
+        let embedding = embedding::l2_normalize(&raw);
+        let quantized_embedding = if request.quantize {
+            embedding::quantize_i8(&embedding)
+        } else {
+            Vec::new()
+        };
+        let reduced_embedding = match &self.projection {
+            Some(projection) if request.reduce_dimensions => {
+                projection.apply(&embedding).map_err(|err| {
+                    self.errors
+                        .fail(Code::Internal, "embedding projection failed", err)
+                })?
+            }
+            _ => Vec::new(),
         };
+        let created_at = unix_millis();
+        info!(%request_id, dims = embedding.len(), "enrollment completed");
+
+        let template = Template {
+            user_id: request.user_id,
+            embedding,
+            model_fingerprint: self.template_fingerprint.clone(),
+            created_at,
+            quantized_embedding,
+            reduced_embedding,
+        };
+        usage.bytes_out = template.encoded_len() as u64;
+        usage.emit(&tenant, "Enroll");
+
+        let mut response = Response::new(template);
+        timing.attach(&mut response);
+        Ok(response)
+    }
 
-        Ok(Response::new(response))
+    // Each image goes through process_image, so it gets its own request ID,
+    // rate limiting and metrics.
+    async fn verify_images(
+        &self,
+        request: Request<VerifyImagesRequest>,
+    ) -> Result<Response<VerifyImagesResponse>, Status> {
+        let metadata = request.metadata().clone();
+        // Items queued behind the concurrency limit share the caller's deadline.
+        let deadline = deadline::from_metadata(&metadata).map(|timeout| Instant::now() + timeout);
+        let images = request.into_inner().images;
+        let mut violations = FieldViolations::default();
+        if images.is_empty() {
+            violations.add("images", "at least one image is required");
+        }
+        if images.len() > self.batch_max_images {
+            violations.add(
+                "images",
+                format!(
+                    "at most {} images are allowed per call",
+                    self.batch_max_images
+                ),
+            );
+        }
+        violations.into_result()?;
+
+        let results = batch::verify_each(images, self.batch_concurrency, move |image| {
+            let mut request = Request::new(image);
+            *request.metadata_mut() = metadata.clone();
+            async move {
+                self.verify_until(request, deadline)
+                    .await
+                    .map(Response::into_inner)
+            }
+        })
+        .await;
+
+        Ok(Response::new(VerifyImagesResponse { results }))
+    }
+
+    async fn compare_images(
+        &self,
+        request: Request<CompareRequest>,
+    ) -> Result<Response<CompareResponse>, Status> {
+        let _permit = self.acquire_permit()?;
+
+        let tenant = usage::tenant(&request);
+        let request = request.into_inner();
+        let request_id = Uuid::new_v4().to_string();
+        let mut violations = FieldViolations::default();
+        if request.image_a.is_empty() {
+            violations.add("image_a", "image data cannot be empty");
+        }
+        if request.image_b.is_empty() {
+            violations.add("image_b", "image data cannot be empty");
+        }
+        violations.check_size("image_a", request.image_a.len(), self.max_image_bytes);
+        violations.check_size("image_b", request.image_b.len(), self.max_image_bytes);
+        violations.into_result()?;
+
+        let mut timing = ServerTiming::default();
+        let mut usage = ResourceUsage::default();
+        let mut tensors = Vec::with_capacity(2);
+        for image_data in [request.image_a, request.image_b] {
+            let preprocessed = self
+                .run_preprocess(
+                    image_data,
+                    &request_id,
+                    PreprocessOptions::default(),
+                    &mut timing,
+                    &mut usage,
+                )
+                .await?
+                .map_err(|err| self.image_error(err))?;
+            tensors.push(preprocessed.tensor);
+        }
+
+        let started = Instant::now();
+        let (embedding_a, embedding_b) = tokio::try_join!(
+            self.triton.infer_with_id(&tensors[0], &request_id),
+            self.triton.infer_with_id(&tensors[1], &request_id),
+        )
+        .map_err(|err| {
+            self.errors
+                .fail_with(err.error_code(), "inference failed", err)
+        })?;
+        timing.record("inference", started.elapsed());
+
+        let similarity =
+            embedding::cosine_similarity(&embedding_a, &embedding_b).ok_or_else(|| {
+                self.errors.fail(
+                    Code::Internal,
+                    "embeddings cannot be compared",
+                    format!(
+                        "embedding dimensions {} and {} differ or one is all zeros",
+                        embedding_a.len(),
+                        embedding_b.len()
+                    ),
+                )
+            })?;
+        let is_match = self.threshold.decide(similarity) == Decision::Accept;
+        info!(%request_id, similarity, is_match, "comparison completed");
+
+        let reply = CompareResponse {
+            request_id,
+            similarity,
+            is_match,
+        };
+        usage.bytes_out = reply.encoded_len() as u64;
+        usage.emit(&tenant, "CompareImages");
+
+        let mut response = Response::new(reply);
+        timing.attach(&mut response);
+        Ok(response)
+    }
+}
+
+struct ModelAdminService {
+    triton: TritonClient,
+    errors: ErrorVerbosity,
+}
+
+impl ModelAdminService {
+    fn validate(&self, request: &ModelAdminRequest) -> Result<(), Status> {
+        let mut violations = FieldViolations::default();
+        if request.model_name.is_empty() {
+            violations.add("model_name", "model_name is required");
+        }
+        violations.into_result()
+    }
+}
+
+#[tonic::async_trait]
+impl ModelAdmin for ModelAdminService {
+    async fn load_model(
+        &self,
+        request: Request<ModelAdminRequest>,
+    ) -> Result<Response<ModelAdminResponse>, Status> {
+        let request = request.into_inner();
+        self.validate(&request)?;
+        self.triton
+            .load_model(&request.model_name)
+            .await
+            .map_err(|err| {
+                self.errors
+                    .fail(Code::Unavailable, "model load failed", err)
+            })?;
+        info!(model_name = %request.model_name, "model loaded");
+
+        Ok(Response::new(ModelAdminResponse {
+            message: format!("model '{}' loaded", request.model_name),
+        }))
+    }
+
+    async fn unload_model(
+        &self,
+        request: Request<ModelAdminRequest>,
+    ) -> Result<Response<ModelAdminResponse>, Status> {
+        let request = request.into_inner();
+        self.validate(&request)?;
+        self.triton
+            .unload_model(&request.model_name)
+            .await
+            .map_err(|err| {
+                self.errors
+                    .fail(Code::Unavailable, "model unload failed", err)
+            })?;
+        info!(model_name = %request.model_name, "model unloaded");
+
+        Ok(Response::new(ModelAdminResponse {
+            message: format!("model '{}' unloaded", request.model_name),
+        }))
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt()
-        .with_env_filter("info")
-        .with_target(false)
-        .init();
+    // RUST_LOG can lower the level (e.g. "info,rust_service=debug") so sampled
+    // verification detail becomes visible.
+    let subscriber = tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(tracing_subscriber::fmt::layer().with_target(false));
+    #[cfg(feature = "otel")]
+    let (subscriber, _tracer) = match std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+        Ok(endpoint) => (
+            subscriber.with(Some(telemetry::layer(&endpoint)?)),
+            Some(telemetry::TracerGuard),
+        ),
+        Err(_) => (subscriber.with(None), None),
+    };
+    subscriber.init();
 
     let addr: SocketAddr = "0.0.0.0:50051".parse()?;
-    let triton_endpoint =
+    let triton_endpoints =
         std::env::var("TRITON_ENDPOINT").unwrap_or_else(|_| "http://triton:8001".to_string());
+    let mut triton_endpoints = triton_endpoints
+        .split(',')
+        .map(str::trim)
+        .filter(|endpoint| !endpoint.is_empty());
+    let triton_endpoint = triton_endpoints
+        .next()
+        .ok_or("TRITON_ENDPOINT must name at least one endpoint")?
+        .to_string();
     let triton_model =
         std::env::var("TRITON_MODEL_NAME").unwrap_or_else(|_| "face_verification".to_string());
     let triton_input = std::env::var("TRITON_INPUT_NAME").unwrap_or_else(|_| "input".to_string());
@@ -70,27 +1009,494 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .map(|value| matches!(value.as_str(), "1" | "true" | "TRUE" | "True"))
         .unwrap_or(false);
     let triton_ca_cert = std::env::var("TRITON_CA_CERT_PATH").ok();
+    // Thumbnails upscaled to the model input only waste a Triton call.
+    // MIN_IMAGE_DIM takes precedence over the older QUALITY_MIN_DIMENSION; 0
+    // disables the guard.
+    let min_image_dim = match parse_env::<u32>("MIN_IMAGE_DIM")? {
+        Some(min) => min,
+        None => parse_env("QUALITY_MIN_DIMENSION")?.unwrap_or(64),
+    };
+    let quality = QualityGates {
+        min_dimension: (min_image_dim > 0).then_some(min_image_dim),
+        min_variance: parse_env("QUALITY_MIN_VARIANCE")?,
+        max_aspect_ratio: parse_env("QUALITY_MAX_ASPECT_RATIO")?,
+        min_blur_score: parse_env("QUALITY_MIN_BLUR_SCORE")?,
+    };
+
+    let mut triton = TritonClient::new(
+        triton_endpoint,
+        triton_model,
+        triton_input,
+        triton_output,
+        triton_use_tls,
+        triton_ca_cert,
+    );
+    triton = triton
+        .with_local_endpoints(triton_endpoints)
+        .with_pool_size(parse_env("TRITON_POOL_SIZE")?.unwrap_or(1));
+    if let Ok(remote) = std::env::var("TRITON_REMOTE_ENDPOINTS") {
+        triton = triton.with_remote_endpoints(
+            remote
+                .split(',')
+                .map(str::trim)
+                .filter(|endpoint| !endpoint.is_empty()),
+        );
+    }
+    if let Some(raw_input) = parse_env::<bool>("TRITON_RAW_INPUT")? {
+        triton = triton.with_raw_input(raw_input);
+    }
+    if let Some(binary_output) = parse_env::<bool>("TRITON_BINARY_OUTPUT")? {
+        triton = triton.with_binary_output(binary_output);
+    }
+    if let Some(attempts) = parse_env("TRITON_RETRY_ATTEMPTS")? {
+        let base_delay = parse_env("TRITON_RETRY_BASE_DELAY_MS")?.unwrap_or(100);
+        triton = triton.with_retry(attempts, Duration::from_millis(base_delay));
+    }
+    if let Some(checks) = parse_env("TRITON_READY_CHECKS")? {
+        let interval = parse_env("TRITON_READY_INTERVAL_MS")?.unwrap_or(500);
+        triton = triton.with_readiness_wait(checks, Duration::from_millis(interval));
+    }
+    triton = triton.with_client_identity(
+        std::env::var("TRITON_CLIENT_CERT_PATH").ok(),
+        std::env::var("TRITON_CLIENT_KEY_PATH").ok(),
+    )?;
+    triton = triton.with_timeouts(
+        Duration::from_millis(parse_env("TRITON_CONNECT_TIMEOUT_MS")?.unwrap_or(5_000)),
+        Duration::from_millis(parse_env("TRITON_REQUEST_TIMEOUT_MS")?.unwrap_or(15_000)),
+    );
+    if let Ok(version) = std::env::var("TRITON_MODEL_VERSION") {
+        triton = triton.with_model_version(version);
+    }
+    // 0 disables keepalive, as with the server-side GRPC_KEEPALIVE_* settings.
+    let keepalive_interval = parse_env::<u64>("TRITON_KEEPALIVE_INTERVAL_SECS")?.unwrap_or(30);
+    let keepalive_timeout = parse_env::<u64>("TRITON_KEEPALIVE_TIMEOUT_SECS")?.unwrap_or(20);
+    triton = triton.with_keepalive((keepalive_interval > 0).then(|| Keepalive {
+        interval: Duration::from_secs(keepalive_interval),
+        timeout: Duration::from_secs(keepalive_timeout),
+    }));
+    triton = triton.with_compression(parse_env("TRITON_COMPRESSION")?.unwrap_or(false))?;
+    let input_dtype = parse_env("TRITON_INPUT_DTYPE")?.unwrap_or_default();
+    triton = triton.with_input_dtype(input_dtype);
+    if let Some(batch_size) = parse_env("TRITON_PAD_BATCH_SIZE")? {
+        triton = triton.with_batch_padding(batch_size);
+    }
+    if let Ok(path) = std::env::var("MODEL_SPECS_PATH") {
+        let specs = ModelSpecs::load(path)?;
+        info!(models = specs.len(), "loaded model specs");
+        triton = triton.with_model_specs(specs);
+    }
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+    let mut triton_reachable = true;
+    if parse_env::<bool>("TRITON_EAGER_CONNECT")?.unwrap_or(false) {
+        // Keep serving health checks so the failure is visible to probes; the
+        // lazy client retries the connection on the first request.
+        match triton.clone().connect_eager().await {
+            Ok(connected) => triton = connected,
+            Err(err) => {
+                error!("initial Triton connection failed: {err}");
+                triton_reachable = false;
+            }
+        }
+    }
+    health::report_triton(&mut health_reporter, triton_reachable).await;
+    if let Some(interval) = parse_env::<u64>("HEALTH_CHECK_INTERVAL_SECS")? {
+        spawn_health_monitor(
+            triton.clone(),
+            health_reporter,
+            Duration::from_secs(interval),
+        );
+    }
+    let preprocess = PreprocessConfig {
+        input_size: InputSize {
+            width: parse_env("TRITON_INPUT_WIDTH")?.unwrap_or(224),
+            height: parse_env("TRITON_INPUT_HEIGHT")?.unwrap_or(224),
+        },
+        allowed_formats: parse_env("IMAGE_ALLOWED_FORMATS")?.unwrap_or_default(),
+        resize: parse_env("IMAGE_RESIZE_MODE")?.unwrap_or_default(),
+        resize_filter: parse_env("IMAGE_RESIZE_FILTER")?.unwrap_or_default(),
+        channels: parse_env("IMAGE_CHANNEL_MODE")?.unwrap_or_default(),
+        channel_order: parse_env("IMAGE_CHANNEL_ORDER")?.unwrap_or_default(),
+        layout: parse_env("TRITON_INPUT_LAYOUT")?.unwrap_or_default(),
+        center_crop: parse_env("IMAGE_CENTER_CROP")?.unwrap_or(false),
+        background: parse_env("IMAGE_BACKGROUND")?.unwrap_or_default(),
+        normalization: parse_env("IMAGE_NORMALIZATION")?.unwrap_or_default(),
+        quality,
+        truncation: parse_env("IMAGE_TRUNCATION_POLICY")?.unwrap_or_default(),
+        memory_budget: parse_env("MAX_REQUEST_MEMORY_BYTES")?,
+        downscale_only: parse_env("IMAGE_DOWNSCALE_ONLY")?.unwrap_or(false),
+        prescale_jpeg: parse_env("IMAGE_PRESCALE_JPEG")?.unwrap_or(false),
+        pipeline: match std::env::var("PREPROCESS_PIPELINE") {
+            Ok(json) => Some(PreprocessPipeline::from_json(&json)?),
+            Err(_) => None,
+        },
+        ..Default::default()
+    };
+    if input_dtype == InputDtype::Uint8
+        && preprocess.normalization != image::Normalization::IDENTITY
+    {
+        return Err("TRITON_INPUT_DTYPE=UINT8 requires IMAGE_NORMALIZATION=identity".into());
+    }
+    // A pipeline builds its own tensor and would silently stay RGB.
+    if preprocess.pipeline.is_some() && preprocess.channel_order == image::ChannelOrder::Bgr {
+        return Err("IMAGE_CHANNEL_ORDER=bgr is not supported with PREPROCESS_PIPELINE".into());
+    }
+    let dry_run = parse_env::<bool>("DRY_RUN")?.unwrap_or(false);
+    if dry_run {
+        warn!("DRY_RUN is set: verifications are scored from the tensor without Triton");
+    } else {
+        // A mismatch would fail every request, so refuse to start; an
+        // unreachable Triton is left to the health checks as with eager connect.
+        match triton.validate(&preprocess.tensor_shape()).await {
+            Ok(()) => {}
+            Err(err @ TritonError::Configuration(_)) => return Err(err.into()),
+            Err(err) => warn!("could not validate Triton model metadata: {err}"),
+        }
+    }
+    let decision_table = match std::env::var("DECISION_TABLE_PATH") {
+        Ok(path) => Some(DecisionTable::load(path)?),
+        Err(_) => None,
+    };
+    let crop_regions: Vec<CropRegion> = match std::env::var("CROP_REGIONS") {
+        Ok(json) => serde_json::from_str(&json)?,
+        Err(_) => Vec::new(),
+    };
+    let preprocess_timeout = parse_env::<u64>("PREPROCESS_TIMEOUT_MS")?.map(Duration::from_millis);
+    let activation: Activation = parse_env("SCORE_ACTIVATION")?.unwrap_or_default();
 
+    let projection = match std::env::var("EMBEDDING_PROJECTION_PATH") {
+        Ok(path) => {
+            let projection = Projection::load(path)?;
+            info!(
+                input_dim = projection.input_dim(),
+                output_dim = projection.output_dim(),
+                "loaded embedding projection"
+            );
+            Some(projection)
+        }
+        Err(_) => None,
+    };
+    let errors: ErrorVerbosity = parse_env("ERROR_VERBOSITY")?.unwrap_or_default();
+    let admin = parse_env::<bool>("ADMIN_RPC_ENABLED")?
+        .unwrap_or(false)
+        .then(|| {
+            ModelAdminServer::new(ModelAdminService {
+                triton: triton.clone(),
+                errors,
+            })
+        });
+    // Lets grpcurl and similar tools list and describe the verify services.
+    let reflection = if parse_env::<bool>("GRPC_REFLECTION_ENABLED")?.unwrap_or(false) {
+        Some(
+            tonic_reflection::server::Builder::configure()
+                .register_encoded_file_descriptor_set(verify::FILE_DESCRIPTOR_SET)
+                .build()?,
+        )
+    } else {
+        None
+    };
+    let metrics = Arc::new(Metrics::new());
+    let metrics_addr = parse_env("METRICS_ADDR")?.unwrap_or(SocketAddr::from(([0, 0, 0, 0], 9090)));
+    tokio::spawn(metrics::serve(Arc::clone(&metrics), metrics_addr));
+    if let Some(interval) = parse_env::<u64>("BATCH_STATS_INTERVAL_SECS")? {
+        spawn_batch_stats_reporter(
+            triton.clone(),
+            Arc::clone(&metrics),
+            Duration::from_secs(interval),
+        );
+    }
+    let max_image_bytes = parse_env("MAX_IMAGE_BYTES")?.unwrap_or(10 * 1024 * 1024);
+    let mut fetcher = ImageFetcher::new(
+        Duration::from_millis(parse_env("IMAGE_FETCH_TIMEOUT_MS")?.unwrap_or(10_000)),
+        parse_env("IMAGE_CACHE_CAPACITY")?.unwrap_or(0),
+    )?
+    .with_max_bytes(max_image_bytes);
+    if let Some(root) = parse_env::<PathBuf>("IMAGE_FILE_ROOT")? {
+        fetcher = fetcher.with_file_root(root)?;
+    }
+    if let Ok(hosts) = std::env::var("IMAGE_URI_ALLOWED_HOSTS") {
+        fetcher = fetcher.with_allowed_hosts(hosts.split(','));
+    }
+    if parse_env::<bool>("IMAGE_URI_ALLOW_HTTP")?.unwrap_or(false) {
+        fetcher = fetcher.with_plain_http(true);
+    }
+    let score_cache = match parse_env::<usize>("SCORE_CACHE_CAPACITY")? {
+        Some(capacity) if capacity > 0 => {
+            let ttl = parse_env("SCORE_CACHE_TTL_SECS")?.unwrap_or(60);
+            Some(ScoreCache::new(capacity, Duration::from_secs(ttl)))
+        }
+        _ => None,
+    };
+    let rate_limiter = match parse_env::<f64>("USER_RATE_LIMIT_PER_SEC")? {
+        Some(rate) => {
+            let burst = parse_env("USER_RATE_LIMIT_BURST")?.unwrap_or(rate.ceil().max(1.0) as u32);
+            Some(UserRateLimiter::new(rate, burst)?)
+        }
+        None => None,
+    };
+    let liveness = match std::env::var("LIVENESS_MODEL_NAME") {
+        Ok(model) => Some(LivenessCheck::new(
+            triton.for_model(
+                model,
+                std::env::var("LIVENESS_INPUT_NAME").unwrap_or_else(|_| "input".to_string()),
+                std::env::var("LIVENESS_OUTPUT_NAME").unwrap_or_else(|_| "liveness".to_string()),
+            ),
+            parse_env("LIVENESS_THRESHOLD")?.unwrap_or_default(),
+        )),
+        Err(_) => None,
+    };
+    let face_detector = match std::env::var("FACE_DETECTION_MODEL_NAME") {
+        Ok(model) => {
+            let input_size = InputSize {
+                width: parse_env("FACE_DETECTION_INPUT_WIDTH")?.unwrap_or(320),
+                height: parse_env("FACE_DETECTION_INPUT_HEIGHT")?.unwrap_or(320),
+            };
+            let detector = FaceDetector::new(
+                triton.for_model(
+                    model,
+                    std::env::var("FACE_DETECTION_INPUT_NAME")
+                        .unwrap_or_else(|_| "input".to_string()),
+                    std::env::var("FACE_DETECTION_OUTPUT_NAME")
+                        .unwrap_or_else(|_| "boxes".to_string()),
+                ),
+                input_size,
+            )
+            .with_min_confidence(parse_env("FACE_DETECTION_MIN_CONFIDENCE")?.unwrap_or(0.5))
+            .with_margin(parse_env("FACE_CROP_MARGIN")?.unwrap_or(0.2));
+            Some(Arc::new(detector))
+        }
+        Err(_) => None,
+    };
+    let config_fingerprint =
+        config_fingerprint(&triton, &preprocess, activation, face_detector.as_deref());
+    info!(%config_fingerprint, "Resolved model and preprocessing configuration");
+    let template_fingerprint = template_fingerprint(&config_fingerprint, projection.as_ref());
+    let labels = match std::env::var("CLASS_LABELS_PATH") {
+        Ok(path) => {
+            let labels = Labels::load(path)?;
+            info!(classes = labels.len(), "loaded class labels");
+            Some(Arc::new(labels))
+        }
+        Err(_) => None,
+    };
     let service = ImageProcessorService {
-        triton: TritonClient::new(
-            triton_endpoint,
-            triton_model,
-            triton_input,
-            triton_output,
-            triton_use_tls,
-            triton_ca_cert,
-        ),
+        triton,
+        preprocess: Arc::new(preprocess),
+        preprocess_timeout,
+        config_fingerprint,
+        template_fingerprint,
+        decision_table,
+        threshold: parse_env("VERIFY_THRESHOLD")?.unwrap_or_default(),
+        liveness,
+        face_detector,
+        models: parse_env("ALLOWED_MODELS")?.unwrap_or_default(),
+        crop_regions: Arc::new(crop_regions),
+        activation,
+        labels,
+        limiter: parse_env("MAX_CONCURRENT_REQUESTS")?.map(ConcurrencyLimiter::new),
+        rate_limiter,
+        errors,
+        log_sampler: LogSampler::new(parse_env("DEBUG_LOG_SAMPLE_RATE")?.unwrap_or(0.0))?,
+        audit_signer: std::env::var("AUDIT_HMAC_SECRET")
+            .ok()
+            .map(AuditSigner::new),
+        audit_sink: match std::env::var("AUDIT_LOG_PATH") {
+            Ok(path) => Some(Arc::new(JsonLinesAuditSink::open(path)?) as Arc<dyn AuditSink>),
+            Err(_) => None,
+        },
+        projection,
+        drift_threshold: parse_env("EMBEDDING_DRIFT_THRESHOLD")?,
+        metrics,
+        max_image_bytes,
+        score_cache,
+        batch_max_images: parse_env("VERIFY_BATCH_MAX_IMAGES")?.unwrap_or(32),
+        batch_concurrency: parse_env("VERIFY_BATCH_CONCURRENCY")?.unwrap_or(4),
+        fetcher,
+        dry_run,
     };
+    if !dry_run && parse_env::<bool>("WARMUP_ON_START")?.unwrap_or(false) {
+        let strict = parse_env::<bool>("WARMUP_STRICT")?.unwrap_or(false);
+        let shape = service.preprocess.tensor_shape();
+        if shape.iter().any(|&dim| dim < 0) {
+            warn!("skipping warmup: PREPROCESS_PIPELINE has no Resize, so its size varies");
+        } else {
+            match service.triton.warmup(&shape).await {
+                Ok(latency) => info!(
+                    latency_ms = latency.as_millis() as u64,
+                    "warmup inference completed"
+                ),
+                Err(err) if strict => return Err(err.into()),
+                Err(err) => warn!("warmup inference failed: {err}"),
+            }
+        }
+    }
 
-    info!(%addr, "Starting Rust image processor");
+    // Pings idle connections often enough that load balancers with a 60s idle
+    // timeout never see a silent stream; 0 disables keepalive.
+    let keepalive_interval = parse_env::<u64>("GRPC_KEEPALIVE_INTERVAL_SECS")?.unwrap_or(30);
+    let keepalive_timeout = parse_env::<u64>("GRPC_KEEPALIVE_TIMEOUT_SECS")?.unwrap_or(20);
+    let router = Server::builder()
+        .http2_keepalive_interval(
+            (keepalive_interval > 0).then_some(Duration::from_secs(keepalive_interval)),
+        )
+        .http2_keepalive_timeout(Some(Duration::from_secs(keepalive_timeout)))
+        .add_service(health_service)
+        // Room for CompareImages' two images plus framing, so oversized uploads
+        // get the descriptive InvalidArgument instead of a transport error.
+        .add_service(
+            ImageProcessorServer::new(service)
+                .max_decoding_message_size(2 * max_image_bytes + 1024 * 1024),
+        )
+        .add_optional_service(admin)
+        .add_optional_service(reflection);
 
-    if let Err(err) = Server::builder()
-        .add_service(ImageProcessorServer::new(service))
-        .serve(addr)
-        .await
-    {
+    let result = match std::env::var("SERVER_UDS_PATH") {
+        Ok(path) => serve_uds(router, &path).await,
+        Err(_) => {
+            let backlog = parse_env("SERVER_LISTEN_BACKLOG")?.unwrap_or(1024);
+            let accept_rate =
+                parse_env::<u32>("SERVER_MAX_ACCEPTS_PER_SEC")?.filter(|&rate| rate > 0);
+            serve_tcp(router, addr, backlog, accept_rate).await
+        }
+    };
+    if let Err(err) = result {
         error!("server error: {err}");
     }
 
     Ok(())
 }
+
+// Connections beyond the accept rate wait in the kernel backlog rather than
+// being reset, so a larger backlog absorbs short floods.
+async fn serve_tcp(
+    router: Router,
+    addr: SocketAddr,
+    backlog: u32,
+    accept_rate: Option<u32>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    socket.set_reuseaddr(true)?;
+    socket.bind(addr)?;
+    let incoming = TcpListenerStream::new(socket.listen(backlog)?);
+    info!(%addr, backlog, ?accept_rate, "Starting Rust image processor");
+
+    let shutdown = shutdown::signal(std::future::pending());
+    match accept_rate {
+        Some(rate) => {
+            router
+                .serve_with_incoming_shutdown(
+                    incoming.throttle(Duration::from_secs(1) / rate),
+                    shutdown,
+                )
+                .await?
+        }
+        None => {
+            router
+                .serve_with_incoming_shutdown(incoming, shutdown)
+                .await?
+        }
+    }
+    info!("server stopped");
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn serve_uds(router: Router, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // A socket left behind by an unclean exit would make bind fail.
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path)?;
+    info!(path, "Starting Rust image processor on Unix socket");
+
+    let result = router
+        .serve_with_incoming_shutdown(
+            UnixListenerStream::new(listener),
+            shutdown::signal(std::future::pending()),
+        )
+        .await;
+
+    if let Err(err) = std::fs::remove_file(path) {
+        error!(path, "failed to remove Unix socket: {err}");
+    }
+
+    result.map_err(Into::into)
+}
+
+#[cfg(not(unix))]
+async fn serve_uds(_router: Router, _path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    Err("SERVER_UDS_PATH is only supported on Unix platforms".into())
+}
+
+fn spawn_batch_stats_reporter(triton: TritonClient, metrics: Arc<Metrics>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        let mut previous = BatchUtilization::default();
+        loop {
+            ticker.tick().await;
+            match triton.batch_utilization().await {
+                Ok(utilization) => {
+                    metrics.record_batch_utilization(&utilization, &previous);
+                    info!(
+                        average_batch_size = utilization.average_batch_size().unwrap_or_default(),
+                        average_queue_ms = utilization
+                            .average_queue_time
+                            .map(|queue| queue.as_secs_f64() * 1000.0)
+                            .unwrap_or_default(),
+                        executions_by_batch_size = ?utilization.executions_by_batch_size,
+                        "Triton batch utilization"
+                    );
+                    previous = utilization;
+                }
+                Err(err) => warn!("failed to fetch Triton batch statistics: {err}"),
+            }
+        }
+    });
+}
+
+fn spawn_health_monitor(triton: TritonClient, mut reporter: HealthReporter, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let ready = match triton.server_ready().await {
+                Ok(ready) => ready,
+                Err(err) => {
+                    warn!("Triton health check failed: {err}");
+                    false
+                }
+            };
+            health::report_triton(&mut reporter, ready).await;
+        }
+    });
+}
+
+// A blurry photo only needs a steadier shot, not a different one.
+fn quality_message(reason: &QualityRejection) -> &'static str {
+    match reason {
+        QualityRejection::Blurry { .. } => "Image is too blurry, please hold the camera still",
+        _ => "No usable face detected, please retake the photo",
+    }
+}
+
+fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+fn parse_env<T>(name: &str) -> Result<Option<T>, Box<dyn std::error::Error>>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    match std::env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|err| format!("invalid {name}: {err}").into()),
+        Err(_) => Ok(None),
+    }
+}
@@ -0,0 +1,186 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Arc, time::Duration};
+
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, StatusCode,
+};
+use prometheus::{
+    Encoder, Gauge, Histogram, HistogramOpts, HistogramVec, IntCounter, Opts, Registry, TextEncoder,
+};
+use tracing::{error, info};
+
+use crate::{timing::ServerTiming, triton_client::BatchUtilization};
+
+// Owns its registry rather than using the process-wide default so tests can
+// create independent instances.
+pub struct Metrics {
+    registry: Registry,
+    stage_seconds: HistogramVec,
+    verifications: IntCounter,
+    succeeded: IntCounter,
+    failed: IntCounter,
+    batch_size: Histogram,
+    batch_queue_seconds: Gauge,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let stage_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "verification_stage_seconds",
+                "Time spent in each verification stage",
+            )
+            .buckets(vec![
+                0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+            ]),
+            &["stage"],
+        )
+        .expect("valid histogram definition");
+        let batch_size = Histogram::with_opts(
+            HistogramOpts::new(
+                "triton_batch_size",
+                "Requests per Triton execution, from the dynamic batcher's statistics",
+            )
+            .buckets(vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0]),
+        )
+        .expect("valid histogram definition");
+        let batch_queue_seconds = Gauge::with_opts(Opts::new(
+            "triton_batch_queue_seconds",
+            "Average time a request waited in Triton's batch queue",
+        ))
+        .expect("valid gauge definition");
+        let counter = |name: &str, help: &str| {
+            IntCounter::with_opts(Opts::new(name, help)).expect("valid counter definition")
+        };
+        let metrics = Self {
+            registry: Registry::new(),
+            stage_seconds,
+            verifications: counter("verifications_total", "ProcessImage calls"),
+            succeeded: counter(
+                "verifications_succeeded_total",
+                "ProcessImage calls that verified the user",
+            ),
+            failed: counter(
+                "verifications_failed_total",
+                "ProcessImage calls that failed or did not verify the user",
+            ),
+            batch_size,
+            batch_queue_seconds,
+        };
+        for collector in [
+            Box::new(metrics.stage_seconds.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(metrics.verifications.clone()),
+            Box::new(metrics.succeeded.clone()),
+            Box::new(metrics.failed.clone()),
+            Box::new(metrics.batch_size.clone()),
+            Box::new(metrics.batch_queue_seconds.clone()),
+        ] {
+            metrics
+                .registry
+                .register(collector)
+                .expect("metric names are unique");
+        }
+        metrics
+    }
+
+    pub fn observe_stage(&self, stage: &str, duration: Duration) {
+        self.stage_seconds
+            .with_label_values(&[stage])
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn observe_stages(&self, timing: &ServerTiming) {
+        for (stage, duration) in timing.entries() {
+            self.observe_stage(stage, duration);
+        }
+    }
+
+    pub fn record_verification(&self, success: bool) {
+        self.verifications.inc();
+        if success {
+            self.succeeded.inc();
+        } else {
+            self.failed.inc();
+        }
+    }
+
+    // Triton's counts are cumulative, so only executions since the previous
+    // snapshot are observed. A Triton restart resets them, which saturates to
+    // zero rather than observing negative counts.
+    pub fn record_batch_utilization(
+        &self,
+        current: &BatchUtilization,
+        previous: &BatchUtilization,
+    ) {
+        for (&size, &count) in &current.executions_by_batch_size {
+            let seen = previous
+                .executions_by_batch_size
+                .get(&size)
+                .copied()
+                .unwrap_or(0);
+            for _ in 0..count.saturating_sub(seen) {
+                self.batch_size.observe(size as f64);
+            }
+        }
+        if let Some(queue) = current.average_queue_time {
+            self.batch_queue_seconds.set(queue.as_secs_f64());
+        }
+    }
+
+    pub fn verifications_total(&self) -> u64 {
+        self.verifications.get()
+    }
+
+    // Prometheus text exposition format.
+    pub fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding into a Vec cannot fail");
+        String::from_utf8(buffer).expect("text exposition is UTF-8")
+    }
+
+    fn respond(&self, path: &str) -> hyper::Response<Body> {
+        let builder = hyper::Response::builder();
+        let response = if path == "/metrics" {
+            builder
+                .header(CONTENT_TYPE, TextEncoder::new().format_type())
+                .body(Body::from(self.encode()))
+        } else {
+            builder.status(StatusCode::NOT_FOUND).body(Body::empty())
+        };
+        response.expect("static response parts are valid")
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Plain HTTP on its own port so scrapers need no gRPC support.
+pub async fn serve(metrics: Arc<Metrics>, addr: SocketAddr) {
+    let make_service = make_service_fn(move |_| {
+        let metrics = Arc::clone(&metrics);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request: hyper::Request<Body>| {
+                let response = metrics.respond(request.uri().path());
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+
+    let server = match hyper::Server::try_bind(&addr) {
+        Ok(builder) => builder.serve(make_service),
+        Err(err) => {
+            error!(%addr, "failed to bind metrics endpoint: {err}");
+            return;
+        }
+    };
+    info!(%addr, "Serving Prometheus metrics");
+    if let Err(err) = server.await {
+        error!("metrics endpoint error: {err}");
+    }
+}
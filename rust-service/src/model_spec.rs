@@ -0,0 +1,94 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::triton_client::InputDtype;
+
+#[derive(Debug, Error)]
+pub enum ModelSpecError {
+    #[error("failed to read model specs: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse model specs: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("model '{model}' is invalid: {reason}")]
+    InvalidModel { model: String, reason: String },
+}
+
+// How to call one model: its tensor names, the datatype its input is sent as
+// and, optionally, the per-item input shape (batch dimension excluded, -1
+// matches any size).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ModelSpec {
+    pub input_name: String,
+    pub output_name: String,
+    #[serde(default)]
+    pub datatype: InputDtype,
+    #[serde(default)]
+    pub shape: Option<Vec<i64>>,
+}
+
+impl ModelSpec {
+    pub fn check_shape(&self, tensor_shape: &[i64]) -> Result<(), String> {
+        let Some(expected) = &self.shape else {
+            return Ok(());
+        };
+        let dims = tensor_shape.get(1..).unwrap_or_default();
+        let compatible = dims.len() == expected.len()
+            && expected
+                .iter()
+                .zip(dims)
+                .all(|(&expected, &dim)| expected == -1 || expected == dim);
+        if !compatible {
+            return Err(format!(
+                "input '{}' expects shape {expected:?}, got {tensor_shape:?}",
+                self.input_name
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Model name -> spec, e.g.
+// {"face-v2": {"input_name": "pixels", "output_name": "score", "datatype": "FP16"}}
+#[derive(Debug, Clone, Default)]
+pub struct ModelSpecs {
+    models: HashMap<String, ModelSpec>,
+}
+
+impl ModelSpecs {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ModelSpecError> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ModelSpecError> {
+        let models: HashMap<String, ModelSpec> = serde_json::from_str(json)?;
+        for (model, spec) in &models {
+            let reason = if spec.input_name.is_empty() {
+                "input_name cannot be empty"
+            } else if spec.output_name.is_empty() {
+                "output_name cannot be empty"
+            } else {
+                continue;
+            };
+            return Err(ModelSpecError::InvalidModel {
+                model: model.clone(),
+                reason: reason.to_string(),
+            });
+        }
+        Ok(Self { models })
+    }
+
+    pub fn get(&self, model: &str) -> Option<&ModelSpec> {
+        self.models.get(model)
+    }
+
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+}
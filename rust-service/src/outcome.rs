@@ -0,0 +1,34 @@
+use serde::Serialize;
+
+use crate::{decision::Decision, timing::ServerTiming};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StageTiming {
+    pub stage: &'static str,
+    pub millis: f64,
+}
+
+// Everything a verification produced, in one value that can be logged or
+// queued as JSON. Serialize is not feature-gated: the audit log already needs
+// it for Decision.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VerificationOutcome {
+    pub request_id: String,
+    pub score: f32,
+    pub decision: Decision,
+    pub embedding: Vec<f32>,
+    pub timings: Vec<StageTiming>,
+    pub model_fingerprint: String,
+}
+
+impl VerificationOutcome {
+    pub fn stage_timings(timing: &ServerTiming) -> Vec<StageTiming> {
+        timing
+            .entries()
+            .map(|(stage, duration)| StageTiming {
+                stage,
+                millis: duration.as_secs_f64() * 1000.0,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,229 @@
+use image::{imageops::FilterType, DynamicImage};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::image::{
+    center_crop, to_unit_tensor, CropRegion, ImageError, ImageTensor, Normalization, Rotation,
+};
+
+#[derive(Debug, Error)]
+#[error("invalid preprocessing pipeline: {0}")]
+pub struct PipelineError(String);
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum PreprocessStep {
+    // Fractions of the current image, like CropRegion.
+    Crop {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    Rotate {
+        degrees: u32,
+    },
+    // Centred square of the shorter side.
+    CenterCrop,
+    Resize {
+        width: u32,
+        height: u32,
+    },
+    // Scales pixels to [0, 1] and lays them out channel-first.
+    ToTensor,
+    // Per-channel (value - mean) / std, applied to the tensor.
+    Normalize {
+        mean: [f32; 3],
+        std: [f32; 3],
+    },
+}
+
+// Ordered preprocessing steps. Image steps must precede the single ToTensor
+// step and Normalize must follow it; this is checked once at construction.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "Vec<PreprocessStep>")]
+pub struct PreprocessPipeline {
+    steps: Vec<PreprocessStep>,
+}
+
+// Same rounding as CropRegion::crop.
+fn crop_span(start: f32, length: f32, total: u32) -> u32 {
+    let from = (start.clamp(0.0, 1.0) * total as f32).round() as u32;
+    let to = ((start + length).clamp(0.0, 1.0) * total as f32).round() as u32;
+    to.saturating_sub(from)
+}
+
+enum Stage {
+    Image(DynamicImage),
+    Tensor {
+        data: Vec<f32>,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl Default for PreprocessPipeline {
+    fn default() -> Self {
+        Self {
+            steps: vec![
+                PreprocessStep::Resize {
+                    width: 224,
+                    height: 224,
+                },
+                PreprocessStep::ToTensor,
+            ],
+        }
+    }
+}
+
+impl TryFrom<Vec<PreprocessStep>> for PreprocessPipeline {
+    type Error = PipelineError;
+
+    fn try_from(steps: Vec<PreprocessStep>) -> Result<Self, Self::Error> {
+        Self::new(steps)
+    }
+}
+
+impl PreprocessPipeline {
+    pub fn new(steps: Vec<PreprocessStep>) -> Result<Self, PipelineError> {
+        let mut tensor = false;
+        for step in &steps {
+            match step {
+                PreprocessStep::ToTensor if tensor => {
+                    return Err(PipelineError("ToTensor may only appear once".into()))
+                }
+                PreprocessStep::ToTensor => tensor = true,
+                PreprocessStep::Normalize { std, .. } => {
+                    if !tensor {
+                        return Err(PipelineError("Normalize must follow ToTensor".into()));
+                    }
+                    if std.iter().any(|&value| value == 0.0) {
+                        return Err(PipelineError("Normalize std must be non-zero".into()));
+                    }
+                }
+                _ if tensor => {
+                    return Err(PipelineError(format!(
+                        "{step:?} operates on the image and must precede ToTensor"
+                    )))
+                }
+                PreprocessStep::Rotate { degrees } => {
+                    Rotation::try_from(*degrees).map_err(PipelineError)?;
+                }
+                PreprocessStep::Resize { width, height } if *width == 0 || *height == 0 => {
+                    return Err(PipelineError("Resize dimensions must be non-zero".into()))
+                }
+                _ => {}
+            }
+        }
+        if !tensor {
+            return Err(PipelineError(
+                "pipeline must contain a ToTensor step".into(),
+            ));
+        }
+
+        Ok(Self { steps })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PipelineError> {
+        serde_json::from_str(json).map_err(|err| PipelineError(err.to_string()))
+    }
+
+    pub fn steps(&self) -> &[PreprocessStep] {
+        &self.steps
+    }
+
+    // Width and height of the tensor, known once a Resize fixes them; before
+    // that they follow the uploaded image.
+    pub fn output_size(&self) -> Option<(u32, u32)> {
+        let mut size = None;
+        for step in &self.steps {
+            size = match step {
+                PreprocessStep::Resize { width, height } => Some((*width, *height)),
+                PreprocessStep::Rotate { degrees: 90 | 270 } => size.map(|(w, h)| (h, w)),
+                PreprocessStep::CenterCrop => size.map(|(w, h): (u32, u32)| (w.min(h), w.min(h))),
+                PreprocessStep::Crop {
+                    x,
+                    y,
+                    width,
+                    height,
+                } => size.map(|(w, h)| (crop_span(*x, *width, w), crop_span(*y, *height, h))),
+                _ => size,
+            };
+        }
+        size
+    }
+
+    pub fn run(&self, image: &DynamicImage) -> Result<ImageTensor, ImageError> {
+        let mut stage = Stage::Image(image.clone());
+        for step in &self.steps {
+            stage = match (stage, step) {
+                (
+                    Stage::Image(image),
+                    PreprocessStep::Crop {
+                        x,
+                        y,
+                        width,
+                        height,
+                    },
+                ) => {
+                    let region = CropRegion {
+                        name: "pipeline".to_string(),
+                        x: *x,
+                        y: *y,
+                        width: *width,
+                        height: *height,
+                    };
+                    Stage::Image(region.crop(&image)?)
+                }
+                (Stage::Image(image), PreprocessStep::CenterCrop) => {
+                    Stage::Image(center_crop(&image))
+                }
+                (Stage::Image(image), PreprocessStep::Rotate { degrees }) => {
+                    let rotation = Rotation::try_from(*degrees)
+                        .expect("rotation validated when the pipeline was built");
+                    Stage::Image(rotation.apply(image))
+                }
+                (Stage::Image(image), PreprocessStep::Resize { width, height }) => {
+                    Stage::Image(image.resize_exact(*width, *height, FilterType::CatmullRom))
+                }
+                (Stage::Image(image), PreprocessStep::ToTensor) => Stage::Tensor {
+                    data: to_unit_tensor(&image),
+                    width: image.width(),
+                    height: image.height(),
+                },
+                (
+                    Stage::Tensor {
+                        mut data,
+                        width,
+                        height,
+                    },
+                    PreprocessStep::Normalize { mean, std },
+                ) => {
+                    let normalization = Normalization::Fixed {
+                        mean: *mean,
+                        std: *std,
+                    };
+                    normalization.apply_planes(&mut data, (width * height) as usize, 3);
+                    Stage::Tensor {
+                        data,
+                        width,
+                        height,
+                    }
+                }
+                _ => unreachable!("step order validated when the pipeline was built"),
+            };
+        }
+
+        match stage {
+            Stage::Tensor {
+                data,
+                width,
+                height,
+            } => Ok(ImageTensor {
+                shape: vec![1, 3, height as i64, width as i64],
+                data,
+            }),
+            Stage::Image(_) => unreachable!("pipeline always ends with a tensor"),
+        }
+    }
+}
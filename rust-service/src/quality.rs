@@ -0,0 +1,166 @@
+use image::{imageops::FilterType, DynamicImage, GrayImage};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Default)]
+pub struct QualityGates {
+    pub min_dimension: Option<u32>,
+    pub min_variance: Option<f32>,
+    pub max_aspect_ratio: Option<f32>,
+    // Minimum variance of the Laplacian, see `blur_score`.
+    pub min_blur_score: Option<f32>,
+}
+
+// Heuristic subscores in [0, 1], higher is better, independent of the model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageQuality {
+    pub overall: f32,
+    pub sharpness: f32,
+    pub brightness: f32,
+    pub contrast: f32,
+}
+
+#[derive(Debug, Error)]
+pub enum QualityRejection {
+    #[error("image is {width}x{height}, below the minimum dimension of {min}")]
+    TooSmall { width: u32, height: u32, min: u32 },
+    #[error("pixel variance {variance:.2} is below the minimum of {min:.2}")]
+    LowVariance { variance: f32, min: f32 },
+    #[error("blur score {score:.2} is below the minimum of {min:.2}")]
+    Blurry { score: f32, min: f32 },
+    #[error("aspect ratio {ratio:.2} exceeds the maximum of {max:.2}")]
+    AspectRatio { ratio: f32, max: f32 },
+    #[error(
+        "image is {width}x{height}, smaller than the {target_width}x{target_height} model input"
+    )]
+    WouldUpscale {
+        width: u32,
+        height: u32,
+        target_width: u32,
+        target_height: u32,
+    },
+}
+
+impl QualityGates {
+    pub fn check(&self, image: &DynamicImage) -> Result<(), QualityRejection> {
+        let (width, height) = (image.width(), image.height());
+
+        if let Some(min) = self.min_dimension {
+            if width < min || height < min {
+                return Err(QualityRejection::TooSmall { width, height, min });
+            }
+        }
+
+        if let Some(max) = self.max_aspect_ratio {
+            let ratio = width.max(height) as f32 / width.min(height).max(1) as f32;
+            if ratio > max {
+                return Err(QualityRejection::AspectRatio { ratio, max });
+            }
+        }
+
+        if let Some(min) = self.min_variance {
+            let variance = luma_variance(image);
+            if variance < min {
+                return Err(QualityRejection::LowVariance { variance, min });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn check_blur(
+        &self,
+        image: &DynamicImage,
+        width: u32,
+        height: u32,
+    ) -> Result<(), QualityRejection> {
+        let Some(min) = self.min_blur_score else {
+            return Ok(());
+        };
+        let score = blur_score(image, width, height);
+        if score < min {
+            return Err(QualityRejection::Blurry { score, min });
+        }
+        Ok(())
+    }
+}
+
+// Variance of the Laplacian of the image resized to `width` x `height`, so the
+// same threshold holds whatever resolution the photo was uploaded at.
+pub fn blur_score(image: &DynamicImage, width: u32, height: u32) -> f32 {
+    let resized = image.resize_exact(width, height, FilterType::Triangle);
+    laplacian_variance(&resized.to_luma8())
+}
+
+// Laplacian variance of 100 is a common blur threshold, so it maps to 0.5.
+const SHARPNESS_SCALE: f32 = 100.0;
+
+pub fn assess(image: &DynamicImage) -> ImageQuality {
+    let gray = image.to_luma8();
+    let (mean, variance) = luma_stats(&gray);
+
+    let laplacian = laplacian_variance(&gray);
+    let sharpness = laplacian / (laplacian + SHARPNESS_SCALE);
+    // Mid-grey exposure scores best, pure black or white scores zero.
+    let brightness = 1.0 - (mean / 255.0 - 0.5).abs() * 2.0;
+    let contrast = (variance.sqrt() / 128.0).min(1.0);
+
+    ImageQuality {
+        // A photo is only as usable as its weakest aspect.
+        overall: sharpness.min(brightness).min(contrast),
+        sharpness,
+        brightness,
+        contrast,
+    }
+}
+
+fn laplacian_variance(gray: &GrayImage) -> f32 {
+    let (width, height) = gray.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let at = |x: u32, y: u32| gray.get_pixel(x, y)[0] as f64;
+    let mut count = 0.0;
+    let (mut sum, mut sum_sq) = (0.0f64, 0.0f64);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let value = at(x - 1, y) + at(x + 1, y) + at(x, y - 1) + at(x, y + 1) - 4.0 * at(x, y);
+            sum += value;
+            sum_sq += value * value;
+            count += 1.0;
+        }
+    }
+    let mean = sum / count;
+
+    (sum_sq / count - mean * mean) as f32
+}
+
+fn luma_variance(image: &DynamicImage) -> f32 {
+    luma_stats(&image.to_luma8()).1
+}
+
+fn luma_stats(gray: &GrayImage) -> (f32, f32) {
+    let count = gray.len() as f64;
+    if count == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let (sum, sum_sq) = gray.iter().fold((0.0f64, 0.0f64), |(sum, sum_sq), &value| {
+        let value = value as f64;
+        (sum + value, sum_sq + value * value)
+    });
+    let mean = sum / count;
+
+    (mean as f32, (sum_sq / count - mean * mean) as f32)
+}
+
+impl From<ImageQuality> for crate::verify::ImageQuality {
+    fn from(quality: ImageQuality) -> Self {
+        Self {
+            overall: quality.overall,
+            sharpness: quality.sharpness,
+            brightness: quality.brightness,
+            contrast: quality.contrast,
+        }
+    }
+}
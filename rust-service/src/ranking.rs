@@ -0,0 +1,56 @@
+use crate::decision::Decision;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub id: String,
+    pub score: f32,
+}
+
+// Top-k result of a 1:N comparison. The margin between the best and the
+// runner-up is what separates a confident match from an ambiguous one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ranking {
+    pub matches: Vec<Candidate>,
+    pub top_score: Option<f32>,
+    pub second_score: Option<f32>,
+    pub margin: Option<f32>,
+}
+
+pub fn rank(mut candidates: Vec<Candidate>, k: usize) -> Ranking {
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let top_score = candidates.first().map(|candidate| candidate.score);
+    let second_score = candidates.get(1).map(|candidate| candidate.score);
+    let margin = top_score
+        .zip(second_score)
+        .map(|(top, second)| top - second);
+    candidates.truncate(k);
+
+    Ranking {
+        matches: candidates,
+        top_score,
+        second_score,
+        margin,
+    }
+}
+
+// The k highest scores with their positions in `scores`, best first. Equal
+// scores keep their original order.
+pub fn top_k(scores: &[f32], k: usize) -> Vec<(usize, f32)> {
+    let mut indexed: Vec<(usize, f32)> = scores.iter().copied().enumerate().collect();
+    indexed.sort_by(|a, b| b.1.total_cmp(&a.1));
+    indexed.truncate(k);
+    indexed
+}
+
+// An accepted match whose runner-up is too close goes to manual review; a
+// single-candidate gallery has no runner-up and is left alone.
+pub fn apply_min_margin(
+    decision: Decision,
+    ranking: &Ranking,
+    min_margin: Option<f32>,
+) -> Decision {
+    match (decision, ranking.margin, min_margin) {
+        (Decision::Accept, Some(margin), Some(min)) if margin < min => Decision::Review,
+        _ => decision,
+    }
+}
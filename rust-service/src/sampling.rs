@@ -0,0 +1,27 @@
+use uuid::Uuid;
+
+// The leading 48 bits of a v4 UUID are fully random (the version and variant
+// bits come later), so they make a free, uniform sampling key.
+const SAMPLE_BITS: u32 = 48;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogSampler {
+    threshold: u64,
+}
+
+impl LogSampler {
+    pub fn new(rate: f64) -> Result<Self, String> {
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(format!("sample rate must be within [0, 1], got {rate}"));
+        }
+
+        Ok(Self {
+            threshold: (rate * (1u64 << SAMPLE_BITS) as f64) as u64,
+        })
+    }
+
+    pub fn should_sample(&self, request_id: &Uuid) -> bool {
+        let bucket = (request_id.as_u128() >> (128 - SAMPLE_BITS)) as u64;
+        bucket < self.threshold
+    }
+}
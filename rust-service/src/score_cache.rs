@@ -0,0 +1,128 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use sha2::{Digest, Sha256};
+
+pub type CacheKey = [u8; 32];
+
+// Inference results keyed by a hash of the raw image bytes plus a variant
+// naming everything else that changes the scores (model, rotation, ...).
+// Least recently used entries go first once `capacity` is reached, and entries
+// older than `ttl` are never served.
+pub struct ScoreCache {
+    capacity: usize,
+    ttl: Duration,
+    state: Mutex<CacheState>,
+}
+
+// Recency is tracked without searching the queue: every use appends the key
+// with a fresh generation, and queued positions whose generation an entry has
+// since moved past are stale and skipped.
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    order: VecDeque<(CacheKey, u64)>,
+    generation: u64,
+}
+
+struct CacheEntry {
+    scores: Arc<Vec<f32>>,
+    stored: Instant,
+    generation: u64,
+}
+
+impl CacheState {
+    fn touch(&mut self, key: CacheKey) {
+        self.generation += 1;
+        let Some(entry) = self.entries.get_mut(&key) else {
+            return;
+        };
+        entry.generation = self.generation;
+        self.order.push_back((key, self.generation));
+        // Dropping stale positions once they outnumber live ones keeps the
+        // queue bounded at an amortized O(1) per use.
+        if self.order.len() > 2 * self.entries.len() {
+            let entries = &self.entries;
+            self.order
+                .retain(|(key, generation)| Self::is_current(entries, key, *generation));
+        }
+    }
+
+    fn evict_oldest(&mut self) -> bool {
+        while let Some((key, generation)) = self.order.pop_front() {
+            if Self::is_current(&self.entries, &key, generation) {
+                self.entries.remove(&key);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn is_current(
+        entries: &HashMap<CacheKey, CacheEntry>,
+        key: &CacheKey,
+        generation: u64,
+    ) -> bool {
+        entries
+            .get(key)
+            .is_some_and(|entry| entry.generation == generation)
+    }
+}
+
+impl ScoreCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            state: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+                generation: 0,
+            }),
+        }
+    }
+
+    pub fn key(image: &[u8], variant: &str) -> CacheKey {
+        let mut hasher = Sha256::new();
+        hasher.update(variant.as_bytes());
+        hasher.update([0]);
+        hasher.update(image);
+        hasher.finalize().into()
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<Arc<Vec<f32>>> {
+        let mut state = self.state.lock().expect("score cache lock poisoned");
+        let entry = state.entries.get(key)?;
+        if entry.stored.elapsed() >= self.ttl {
+            state.entries.remove(key);
+            return None;
+        }
+        let scores = Arc::clone(&entry.scores);
+        state.touch(*key);
+        Some(scores)
+    }
+
+    pub fn insert(&self, key: CacheKey, scores: Vec<f32>) -> Arc<Vec<f32>> {
+        let scores = Arc::new(scores);
+        if self.capacity == 0 {
+            return scores;
+        }
+
+        let mut state = self.state.lock().expect("score cache lock poisoned");
+        if !state.entries.contains_key(&key) {
+            while state.entries.len() >= self.capacity && state.evict_oldest() {}
+        }
+        state.entries.insert(
+            key,
+            CacheEntry {
+                scores: Arc::clone(&scores),
+                stored: Instant::now(),
+                generation: 0,
+            },
+        );
+        state.touch(key);
+        scores
+    }
+}
@@ -0,0 +1,46 @@
+use std::future::Future;
+
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownReason {
+    Terminate,
+    Interrupt,
+    Requested,
+}
+
+// Resolves on SIGTERM, ctrl-c or when `trigger` completes, whichever is first.
+pub async fn wait_for(trigger: impl Future<Output = ()>) -> ShutdownReason {
+    tokio::select! {
+        _ = terminate() => ShutdownReason::Terminate,
+        _ = tokio::signal::ctrl_c() => ShutdownReason::Interrupt,
+        _ = trigger => ShutdownReason::Requested,
+    }
+}
+
+// Shutdown future for tonic's serve_with_shutdown: new connections are
+// refused once it resolves while in-flight requests run to completion.
+pub async fn signal(trigger: impl Future<Output = ()>) {
+    let reason = wait_for(trigger).await;
+    info!(?reason, "shutting down, draining in-flight requests");
+}
+
+#[cfg(unix)]
+async fn terminate() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut stream) => {
+            stream.recv().await;
+        }
+        Err(err) => {
+            warn!("failed to install SIGTERM handler: {err}");
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn terminate() {
+    std::future::pending::<()>().await;
+}
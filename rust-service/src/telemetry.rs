@@ -0,0 +1,66 @@
+use opentelemetry::{
+    global,
+    propagation::{Extractor, TextMapPropagator},
+    trace::TraceError,
+    Context,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{propagation::TraceContextPropagator, runtime, trace::Tracer};
+use tonic::metadata::{KeyRef, MetadataMap};
+use tracing::{Span, Subscriber};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+// Exports spans over OTLP/gRPC. The service name comes from OTEL_SERVICE_NAME
+// through the SDK's default resource.
+pub fn layer<S>(endpoint: &str) -> Result<OpenTelemetryLayer<S, Tracer>, TraceError>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .install_batch(runtime::Tokio)?;
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+// Flushes spans still queued in the batch exporter when dropped.
+pub struct TracerGuard;
+
+impl Drop for TracerGuard {
+    fn drop(&mut self) {
+        global::shutdown_tracer_provider();
+    }
+}
+
+pub struct MetadataExtractor<'a>(pub &'a MetadataMap);
+
+impl Extractor for MetadataExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .keys()
+            .filter_map(|key| match key {
+                KeyRef::Ascii(key) => Some(key.as_str()),
+                KeyRef::Binary(_) => None,
+            })
+            .collect()
+    }
+}
+
+// The caller's W3C traceparent/tracestate. Without one the returned context
+// is empty and the request starts a new trace.
+pub fn parent_context(metadata: &MetadataMap) -> Context {
+    TraceContextPropagator::new().extract(&MetadataExtractor(metadata))
+}
+
+pub fn set_parent(span: &Span, metadata: &MetadataMap) {
+    span.set_parent(parent_context(metadata));
+}
@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use tonic::{metadata::MetadataValue, Response};
+
+pub const SERVER_TIMING_HEADER: &str = "server-timing";
+
+#[derive(Debug, Default, Clone)]
+pub struct ServerTiming {
+    entries: Vec<(&'static str, Duration)>,
+}
+
+impl ServerTiming {
+    pub fn record(&mut self, name: &'static str, duration: Duration) {
+        self.entries.push((name, duration));
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&'static str, Duration)> + '_ {
+        self.entries.iter().copied()
+    }
+
+    // Formatted per the W3C Server-Timing header, e.g. "decode;dur=1.250".
+    pub fn header_value(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(name, duration)| format!("{name};dur={:.3}", duration.as_secs_f64() * 1000.0))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn attach<T>(&self, response: &mut Response<T>) {
+        if self.entries.is_empty() {
+            return;
+        }
+        if let Ok(value) = self.header_value().parse::<MetadataValue<_>>() {
+            response.metadata_mut().insert(SERVER_TIMING_HEADER, value);
+        }
+    }
+}
@@ -1,17 +1,40 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant, SystemTime},
+};
 
 use byteorder::{ByteOrder, LittleEndian};
+use half::f16;
 use http::Uri;
+use serde::Deserialize;
 use thiserror::Error;
 use tokio::sync::Mutex;
-use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint};
+use tokio_stream::{Stream, StreamExt};
+#[cfg(feature = "gzip")]
+use tonic::codec::CompressionEncoding;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+use tonic::Code;
+use tracing::{info, warn};
 
-use crate::image::ImageTensor;
+use crate::{
+    endpoints::{self, EndpointHealth, EndpointStatus},
+    errors::ErrorCode,
+    image::ImageTensor,
+    model_spec::ModelSpecs,
+};
 
 pub mod inference {
     tonic::include_proto!("inference");
 }
 
+// How often a lazily connected endpoint re-reads the CA file's mtime.
+const CA_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
 use inference::grpc_inference_service_client::GrpcInferenceServiceClient;
 use inference::model_infer_request::{InferInputTensor, InferRequestedOutputTensor};
 use inference::{InferParameter, InferTensorContents, ModelInferRequest};
@@ -24,17 +47,339 @@ pub enum TritonError {
     InvalidResponse(String),
     #[error("invalid Triton configuration: {0}")]
     Configuration(String),
+    #[error("caller deadline exceeded before Triton answered")]
+    DeadlineExceeded,
+}
+
+impl TritonError {
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            Self::Transport(_) => ErrorCode::TritonUnavailable,
+            Self::Configuration(_) => ErrorCode::TritonMisconfigured,
+            Self::InvalidResponse(_) => ErrorCode::TritonInvalidResponse,
+            Self::DeadlineExceeded => ErrorCode::TritonDeadlineExceeded,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            base_delay: Duration::from_millis(100),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // base * 2^(attempt - 1), with the upper half jittered so clients that
+    // failed together do not retry in lockstep.
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.base_delay.saturating_mul(1 << (attempt - 1).min(16));
+        backoff / 2 + backoff.mul_f64(rand::random::<f64>() / 2.0)
+    }
+}
+
+// PEM files presented to Triton when it requires mutual TLS.
+#[derive(Debug, Clone)]
+struct ClientIdentity {
+    certificate_path: String,
+    key_path: String,
+}
+
+struct SendFailure {
+    error: TritonError,
+    retryable: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum InputDtype {
+    #[default]
+    Fp32,
+    // Always sent as raw bytes: InferTensorContents has no half-precision field.
+    Fp16,
+    // Pixel values for models that normalize inside the graph. Expects the
+    // identity normalization, whose [0, 1] values map back to exact bytes.
+    Uint8,
+}
+
+impl FromStr for InputDtype {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_uppercase().as_str() {
+            "FP32" => Ok(Self::Fp32),
+            "FP16" => Ok(Self::Fp16),
+            "UINT8" => Ok(Self::Uint8),
+            other => Err(format!("unsupported input datatype '{other}'")),
+        }
+    }
+}
+
+impl InputDtype {
+    // Triton's name for the datatype.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Fp32 => "FP32",
+            Self::Fp16 => "FP16",
+            Self::Uint8 => "UINT8",
+        }
+    }
+}
+
+// HTTP/2 pings on the Triton channel, sent even while no call is in flight so
+// load balancers never see the connection as idle and drop it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keepalive {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for Keepalive {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+// Models a caller may pick per request instead of the configured one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelAllowlist(Vec<String>);
+
+impl ModelAllowlist {
+    pub fn new<I, S>(models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self(models.into_iter().map(Into::into).collect())
+    }
+
+    pub fn check(&self, model: &str) -> Result<(), String> {
+        if self.0.iter().any(|allowed| allowed == model) {
+            Ok(())
+        } else {
+            Err(format!("model '{model}' is not allowed"))
+        }
+    }
+}
+
+impl FromStr for ModelAllowlist {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|model| !model.is_empty()),
+        ))
+    }
+}
+
+// Scores as Triton shaped them: outputs with a leading batch dimension keep
+// one row per batch element instead of being flattened together.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InferOutput {
+    Flat(Vec<f32>),
+    Batched(Vec<Vec<f32>>),
+}
+
+impl InferOutput {
+    fn from_shape(scores: Vec<f32>, shape: &[i64]) -> Result<Self, TritonError> {
+        let (rows, rest) = match shape {
+            [rows, rest @ ..] if !rest.is_empty() => (*rows, rest),
+            _ => return Ok(Self::Flat(scores)),
+        };
+        let mismatch = || {
+            TritonError::InvalidResponse(format!(
+                "output shape {shape:?} does not match {} returned values",
+                scores.len()
+            ))
+        };
+        // The shape comes from the server, so a negative or overflowing
+        // dimension is a bad response rather than something to multiply out.
+        if rows < 0 || rest.iter().any(|&dim| dim <= 0) {
+            return Err(mismatch());
+        }
+        let columns = rest
+            .iter()
+            .try_fold(1i64, |product, &dim| product.checked_mul(dim))
+            .ok_or_else(mismatch)?;
+        let values = rows.checked_mul(columns).ok_or_else(mismatch)?;
+        if values as usize != scores.len() {
+            return Err(mismatch());
+        }
+
+        Ok(Self::Batched(
+            scores
+                .chunks(columns as usize)
+                .map(<[f32]>::to_vec)
+                .collect(),
+        ))
+    }
+
+    pub fn into_flat(self) -> Vec<f32> {
+        match self {
+            Self::Flat(scores) => scores,
+            Self::Batched(rows) => rows.concat(),
+        }
+    }
+
+    pub fn into_rows(self) -> Vec<Vec<f32>> {
+        match self {
+            Self::Flat(scores) => vec![scores],
+            Self::Batched(rows) => rows,
+        }
+    }
+
+    fn discard_padding(self, batch: i64, padded_batch: i64) -> Self {
+        match self {
+            Self::Flat(scores) => Self::Flat(discard_padding(scores, batch, padded_batch)),
+            Self::Batched(mut rows) => {
+                if padded_batch > batch && batch > 0 {
+                    rows.truncate(batch as usize);
+                }
+                Self::Batched(rows)
+            }
+        }
+    }
+}
+
+// Cumulative view of how Triton's dynamic batcher has been grouping requests.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchUtilization {
+    pub executions_by_batch_size: BTreeMap<u64, u64>,
+    pub average_queue_time: Option<Duration>,
+}
+
+impl BatchUtilization {
+    pub fn from_statistics(stats: &inference::ModelStatistics) -> Self {
+        let executions_by_batch_size = stats
+            .batch_stats
+            .iter()
+            .filter_map(|batch| {
+                let executions = batch.compute_infer.as_ref()?.count;
+                (executions > 0).then_some((batch.batch_size, executions))
+            })
+            .collect();
+
+        let average_queue_time = stats
+            .inference_stats
+            .as_ref()
+            .and_then(|inference| inference.queue.as_ref())
+            .filter(|queue| queue.count > 0)
+            .map(|queue| Duration::from_nanos(queue.ns / queue.count));
+
+        Self {
+            executions_by_batch_size,
+            average_queue_time,
+        }
+    }
+
+    pub fn average_batch_size(&self) -> Option<f64> {
+        let executions: u64 = self.executions_by_batch_size.values().sum();
+        if executions == 0 {
+            return None;
+        }
+        let requests: u64 = self
+            .executions_by_batch_size
+            .iter()
+            .map(|(size, count)| size * count)
+            .sum();
+        Some(requests as f64 / executions as f64)
+    }
 }
 
 #[derive(Clone)]
 pub struct TritonClient {
-    endpoint: String,
+    endpoints: Arc<Vec<EndpointState>>,
+    // Channels kept per endpoint; see with_pool_size.
+    pool_size: usize,
     model_name: String,
     input_name: String,
     output_name: String,
+    // Empty lets Triton apply the model's version policy.
+    model_version: String,
     use_tls: bool,
     ca_certificate_path: Option<String>,
-    channel: Arc<Mutex<Option<GrpcInferenceServiceClient<Channel>>>>,
+    client_identity: Option<ClientIdentity>,
+    connect_timeout: Duration,
+    request_timeout: Duration,
+    pad_batch_to: Option<usize>,
+    raw_input: bool,
+    binary_output: bool,
+    input_dtype: InputDtype,
+    #[cfg_attr(not(feature = "gzip"), allow(dead_code))]
+    compression: bool,
+    keepalive: Option<Keepalive>,
+    retry: RetryPolicy,
+    readiness: Option<RetryPolicy>,
+    discovered_names: Arc<RwLock<HashMap<String, TensorNames>>>,
+    model_specs: Arc<ModelSpecs>,
+    deadline: Option<Instant>,
+}
+
+// Tensor names learned from a model's metadata, remembered together with the
+// version that served them so a redeploy triggers rediscovery.
+#[derive(Debug, Clone)]
+struct TensorNames {
+    input: String,
+    output: String,
+    version: String,
+}
+
+struct EndpointState {
+    endpoint: String,
+    local: bool,
+    handle: ClientHandle,
+    next_slot: AtomicUsize,
+    health: EndpointHealth,
+    ca_checked: std::sync::Mutex<Option<Instant>>,
+}
+
+impl EndpointState {
+    fn new(endpoint: String, local: bool, pool_size: usize) -> Self {
+        Self {
+            endpoint,
+            local,
+            handle: ClientHandle::Lazy((0..pool_size).map(|_| Mutex::new(None)).collect()),
+            next_slot: AtomicUsize::new(0),
+            health: EndpointHealth::default(),
+            ca_checked: std::sync::Mutex::new(None),
+        }
+    }
+
+    // Round-robin over the endpoint's channels.
+    fn next_slot(&self, len: usize) -> usize {
+        self.next_slot.fetch_add(1, Ordering::Relaxed) % len
+    }
+}
+
+// One entry per pooled channel. Each is its own HTTP/2 connection, so a burst
+// of large tensors is not queued behind a single TCP stream.
+enum ClientHandle {
+    // Slots connect on first use and are only locked while connecting; calls
+    // run on a cloned client.
+    Lazy(Vec<Mutex<Option<Connection>>>),
+    // Tonic clients are cheap to clone and multiplex over one channel, so an
+    // eagerly connected client needs no lock. It forgoes CA reload and
+    // reconnect-on-unavailable, relying on the channel's own reconnects.
+    Eager(Vec<GrpcInferenceServiceClient<Channel>>),
+}
+
+struct Connection {
+    client: GrpcInferenceServiceClient<Channel>,
+    ca_modified: Option<SystemTime>,
 }
 
 impl TritonClient {
@@ -48,90 +393,974 @@ impl TritonClient {
         ca_certificate_path: Option<String>,
     ) -> Self {
         Self {
-            endpoint: endpoint.into(),
+            endpoints: Arc::new(vec![EndpointState::new(endpoint.into(), true, 1)]),
+            pool_size: 1,
             model_name: model_name.into(),
             input_name: input_name.into(),
             output_name: output_name.into(),
+            model_version: String::new(),
             use_tls,
             ca_certificate_path,
-            channel: Arc::new(Mutex::new(None)),
+            client_identity: None,
+            connect_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(15),
+            pad_batch_to: None,
+            raw_input: false,
+            binary_output: false,
+            input_dtype: InputDtype::default(),
+            compression: false,
+            keepalive: Some(Keepalive::default()),
+            retry: RetryPolicy::default(),
+            readiness: None,
+            discovered_names: Arc::default(),
+            model_specs: Arc::default(),
+            deadline: None,
+        }
+    }
+
+    // Runs one inference on an all-zero tensor so the channel is connected and
+    // the model loaded before real traffic; returns how long that took.
+    pub async fn warmup(&self, shape: &[i64]) -> Result<Duration, TritonError> {
+        let tensor = ImageTensor {
+            shape: shape.to_vec(),
+            data: vec![0.0; shape.iter().product::<i64>().max(0) as usize],
+        };
+        let started = Instant::now();
+        self.infer_with_id(&tensor, "warmup").await?;
+        Ok(started.elapsed())
+    }
+
+    // Another model on the same endpoints and connections, sharing every
+    // transport setting but not the model version.
+    pub fn for_model(
+        &self,
+        model_name: impl Into<String>,
+        input_name: impl Into<String>,
+        output_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            model_name: model_name.into(),
+            input_name: input_name.into(),
+            output_name: output_name.into(),
+            model_version: String::new(),
+            ..self.clone()
+        }
+    }
+
+    pub async fn connect_eager(mut self) -> Result<Self, TritonError> {
+        let mut states = Vec::with_capacity(self.endpoints.len());
+        for state in self.endpoints.iter() {
+            let mut clients = Vec::with_capacity(self.pool_size);
+            for _ in 0..self.pool_size {
+                clients.push(self.connect(&state.endpoint).await?.client);
+            }
+            states.push(EndpointState {
+                handle: ClientHandle::Eager(clients),
+                ..EndpointState::new(state.endpoint.clone(), state.local, 0)
+            });
+        }
+        self.endpoints = Arc::new(states);
+        Ok(self)
+    }
+
+    // Remote endpoints only take traffic once every local endpoint's rolling
+    // health has degraded; see select_endpoint.
+    pub fn with_remote_endpoints<I, S>(self, endpoints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.add_endpoints(endpoints, false)
+    }
+
+    // Further replicas sharing load with the first endpoint. Traffic spreads by
+    // rolling health, and an endpoint failing to connect drops out of rotation
+    // until it recovers.
+    pub fn with_local_endpoints<I, S>(self, endpoints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.add_endpoints(endpoints, true)
+    }
+
+    fn add_endpoints<I, S>(mut self, endpoints: I, local: bool) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut states: Vec<EndpointState> = self
+            .endpoints
+            .iter()
+            .map(|state| EndpointState::new(state.endpoint.clone(), state.local, self.pool_size))
+            .collect();
+        states.extend(
+            endpoints
+                .into_iter()
+                .map(|endpoint| EndpointState::new(endpoint.into(), local, self.pool_size)),
+        );
+        self.endpoints = Arc::new(states);
+        self
+    }
+
+    // Number of channels each endpoint spreads calls over, round-robin. They
+    // are opened lazily, so an idle endpoint holds no more than it has used.
+    pub fn with_pool_size(mut self, size: usize) -> Self {
+        self.pool_size = size.max(1);
+        self.add_endpoints(std::iter::empty::<String>(), true)
+    }
+
+    pub fn pool_size(&self) -> usize {
+        self.pool_size
+    }
+
+    // Channels currently connected across all endpoints.
+    pub async fn open_connections(&self) -> usize {
+        let mut open = 0;
+        for state in self.endpoints.iter() {
+            match &state.handle {
+                ClientHandle::Eager(clients) => open += clients.len(),
+                ClientHandle::Lazy(slots) => {
+                    for slot in slots {
+                        if slot.lock().await.is_some() {
+                            open += 1;
+                        }
+                    }
+                }
+            }
         }
+        open
+    }
+
+    pub async fn batch_utilization(&self) -> Result<BatchUtilization, TritonError> {
+        let request = inference::ModelStatisticsRequest {
+            name: self.model_name.clone(),
+            version: String::new(),
+        };
+        let response = self
+            .client()
+            .await?
+            .model_statistics(request)
+            .await
+            .map_err(|status| TritonError::Transport(status.to_string()))?
+            .into_inner();
+
+        let stats = response
+            .model_stats
+            .iter()
+            .find(|stats| stats.name == self.model_name)
+            .ok_or_else(|| {
+                TritonError::InvalidResponse(format!(
+                    "no statistics reported for model '{}'",
+                    self.model_name
+                ))
+            })?;
+        Ok(BatchUtilization::from_statistics(stats))
+    }
+
+    // Every endpoint hosts its own model repository, so the model is loaded in
+    // all of them to keep failover targets able to serve it.
+    pub async fn load_model(&self, model_name: &str) -> Result<(), TritonError> {
+        for state in self.endpoints.iter() {
+            let request = inference::RepositoryModelLoadRequest {
+                repository_name: String::new(),
+                model_name: model_name.to_string(),
+                parameters: HashMap::new(),
+            };
+            self.endpoint_client(state)
+                .await?
+                .repository_model_load(request)
+                .await
+                .map_err(|status| TritonError::Transport(status.to_string()))?;
+        }
+        self.forget_tensor_names(model_name);
+        Ok(())
+    }
+
+    pub async fn unload_model(&self, model_name: &str) -> Result<(), TritonError> {
+        for state in self.endpoints.iter() {
+            let request = inference::RepositoryModelUnloadRequest {
+                repository_name: String::new(),
+                model_name: model_name.to_string(),
+                parameters: HashMap::new(),
+            };
+            self.endpoint_client(state)
+                .await?
+                .repository_model_unload(request)
+                .await
+                .map_err(|status| TritonError::Transport(status.to_string()))?;
+        }
+        self.forget_tensor_names(model_name);
+        Ok(())
+    }
+
+    pub async fn server_ready(&self) -> Result<bool, TritonError> {
+        let response = self
+            .client()
+            .await?
+            .server_ready(inference::ServerReadyRequest {})
+            .await
+            .map_err(|status| TritonError::Transport(status.to_string()))?;
+        Ok(response.into_inner().ready)
+    }
+
+    pub fn endpoint_health(&self) -> Vec<EndpointStatus> {
+        self.endpoints
+            .iter()
+            .map(|state| EndpointStatus {
+                endpoint: state.endpoint.clone(),
+                local: state.local,
+                success_rate: state.health.success_rate(),
+                latency: state.health.latency(),
+                weight: state.health.weight(state.local),
+            })
+            .collect()
+    }
+
+    pub fn with_batch_padding(mut self, batch_size: usize) -> Self {
+        self.pad_batch_to = Some(batch_size);
+        self
+    }
+
+    pub fn with_raw_input(mut self, enabled: bool) -> Self {
+        self.raw_input = enabled;
+        self
+    }
+
+    // Asks Triton for outputs as little-endian bytes in raw_output_contents,
+    // which is more compact than fp32_contents for large embeddings.
+    pub fn with_binary_output(mut self, enabled: bool) -> Self {
+        self.binary_output = enabled;
+        self
+    }
+
+    // Each new channel polls ServerReady up to `max_checks` times, `interval`
+    // apart, before it is used; Triton reports not-ready while loading models.
+    pub fn with_readiness_wait(mut self, max_checks: u32, interval: Duration) -> Self {
+        self.readiness = Some(RetryPolicy {
+            max_attempts: max_checks.max(1),
+            base_delay: interval,
+        });
+        self
+    }
+
+    // Both paths or neither; a certificate without its key (or the reverse)
+    // is a misconfiguration rather than a request for plain TLS.
+    pub fn with_client_identity(
+        mut self,
+        certificate_path: Option<String>,
+        key_path: Option<String>,
+    ) -> Result<Self, TritonError> {
+        match (certificate_path, key_path) {
+            (Some(certificate_path), Some(key_path)) => {
+                if !self.use_tls {
+                    return Err(TritonError::Configuration(
+                        "a client certificate requires TLS to be enabled".to_string(),
+                    ));
+                }
+                self.client_identity = Some(ClientIdentity {
+                    certificate_path,
+                    key_path,
+                });
+                Ok(self)
+            }
+            (None, None) => Ok(self),
+            (Some(_), None) => Err(TritonError::Configuration(
+                "client certificate given without a private key".to_string(),
+            )),
+            (None, Some(_)) => Err(TritonError::Configuration(
+                "client private key given without a certificate".to_string(),
+            )),
+        }
+    }
+
+    // Applied to channels created after this call.
+    pub fn with_timeouts(mut self, connect: Duration, request: Duration) -> Self {
+        self.connect_timeout = connect;
+        self.request_timeout = request;
+        self
+    }
+
+    // `None` disables keepalive pings.
+    pub fn with_keepalive(mut self, keepalive: Option<Keepalive>) -> Self {
+        self.keepalive = keepalive;
+        self
+    }
+
+    pub fn keepalive(&self) -> Option<Keepalive> {
+        self.keepalive
+    }
+
+    pub fn has_client_identity(&self) -> bool {
+        self.client_identity.is_some()
+    }
+
+    pub fn with_model_version(mut self, version: impl Into<String>) -> Self {
+        self.model_version = version.into();
+        self
+    }
+
+    pub fn with_input_dtype(mut self, dtype: InputDtype) -> Self {
+        self.input_dtype = dtype;
+        self
+    }
+
+    // gzip cuts the bandwidth of large tensors to a remote Triton at the cost
+    // of CPU on both ends, and Triton must be built with compression support.
+    pub fn with_compression(mut self, enabled: bool) -> Result<Self, TritonError> {
+        if enabled && !cfg!(feature = "gzip") {
+            return Err(TritonError::Configuration(
+                "TRITON_COMPRESSION needs the service built with the `gzip` feature; \
+                 compression trades CPU on both ends for bandwidth, so it is off by default"
+                    .into(),
+            ));
+        }
+        self.compression = enabled;
+        Ok(self)
+    }
+
+    // Models listed here are called with their configured tensor names and
+    // datatype; any other model falls back to metadata discovery.
+    pub fn with_model_specs(mut self, specs: ModelSpecs) -> Self {
+        self.model_specs = Arc::new(specs);
+        self
+    }
+
+    // Inference gives up at `deadline`: each call carries the remaining time as
+    // its grpc-timeout so Triton can drop the work too, is cut off locally when
+    // it runs out, and is not retried past it. The request timeout still caps
+    // every call.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    // `max_attempts` counts the first try; 1 disables retries.
+    pub fn with_retry(mut self, max_attempts: u32, base_delay: Duration) -> Self {
+        self.retry = RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            base_delay,
+        };
+        self
+    }
+
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    pub fn model_version(&self) -> &str {
+        &self.model_version
+    }
+
+    pub fn input_name(&self) -> &str {
+        &self.input_name
+    }
+
+    pub fn output_name(&self) -> &str {
+        &self.output_name
+    }
+
+    pub fn input_dtype(&self) -> InputDtype {
+        self.input_dtype
     }
 
     pub async fn infer(&self, tensor: &ImageTensor) -> Result<Vec<f32>, TritonError> {
+        self.infer_with_id(tensor, "").await
+    }
+
+    pub async fn infer_with_id(
+        &self,
+        tensor: &ImageTensor,
+        request_id: &str,
+    ) -> Result<Vec<f32>, TritonError> {
+        self.infer_with_version(tensor, request_id, None).await
+    }
+
+    // `version` overrides the configured model version for this call only.
+    pub async fn infer_with_version(
+        &self,
+        tensor: &ImageTensor,
+        request_id: &str,
+        version: Option<&str>,
+    ) -> Result<Vec<f32>, TritonError> {
+        let (output, _) = self
+            .infer_named(
+                &self.model_name,
+                version.unwrap_or(&self.model_version),
+                &self.input_name,
+                &self.output_name,
+                tensor,
+                request_id,
+            )
+            .await?;
+        Ok(output.into_flat())
+    }
+
+    // One score vector per batch element; a 1D output counts as a single element.
+    pub async fn infer_batched(
+        &self,
+        tensor: &ImageTensor,
+        request_id: &str,
+    ) -> Result<Vec<Vec<f32>>, TritonError> {
+        let (output, _) = self
+            .infer_named(
+                &self.model_name,
+                &self.model_version,
+                &self.input_name,
+                &self.output_name,
+                tensor,
+                request_id,
+            )
+            .await?;
+        Ok(output.into_rows())
+    }
+
+    // Checks the configured tensor names and the preprocess shape against the
+    // model's metadata. The batch dimension is skipped since it varies per
+    // request, and -1 on either side matches any size.
+    pub async fn validate(&self, tensor_shape: &[i64]) -> Result<(), TritonError> {
+        let request = inference::ModelMetadataRequest {
+            name: self.model_name.clone(),
+            version: self.model_version.clone(),
+        };
+        let metadata = self
+            .client()
+            .await?
+            .model_metadata(request)
+            .await
+            .map_err(|status| TritonError::Transport(status.to_string()))?
+            .into_inner();
+
+        let find = |tensors: &[inference::model_metadata_response::TensorMetadata],
+                    name: &str,
+                    kind: &str| {
+            tensors
+                .iter()
+                .find(|tensor| tensor.name == name)
+                .cloned()
+                .ok_or_else(|| {
+                    let available: Vec<&str> =
+                        tensors.iter().map(|tensor| tensor.name.as_str()).collect();
+                    TritonError::Configuration(format!(
+                        "model '{}' has no {kind} tensor '{name}' (available: {})",
+                        self.model_name,
+                        available.join(", ")
+                    ))
+                })
+        };
+        let input = find(&metadata.inputs, &self.input_name, "input")?;
+        find(&metadata.outputs, &self.output_name, "output")?;
+
+        let expected = tensor_shape.get(1..).unwrap_or_default();
+        let dims = match input.shape.len() {
+            len if len == expected.len() => &input.shape[..],
+            len if len == expected.len() + 1 => &input.shape[1..],
+            _ => &[][..],
+        };
+        let compatible = dims.len() == expected.len()
+            && dims
+                .iter()
+                .zip(expected)
+                .all(|(&dim, &expected)| dim == -1 || expected == -1 || dim == expected);
+        if !compatible {
+            return Err(TritonError::Configuration(format!(
+                "input tensor '{}' expects shape {:?}, but preprocessing produces {:?}",
+                self.input_name, input.shape, tensor_shape
+            )));
+        }
+        Ok(())
+    }
+
+    // Runs another model on the same endpoints, discovering its input and
+    // output tensor names from Triton instead of requiring them in config.
+    pub async fn infer_with_model(
+        &self,
+        model_name: &str,
+        tensor: &ImageTensor,
+        request_id: &str,
+    ) -> Result<Vec<f32>, TritonError> {
+        if let Some(spec) = self.model_specs.get(model_name) {
+            spec.check_shape(&tensor.shape)
+                .map_err(|reason| TritonError::Configuration(format!("{model_name}: {reason}")))?;
+            let client = self
+                .for_model(model_name, &spec.input_name, &spec.output_name)
+                .with_input_dtype(spec.datatype);
+            let (output, _) = client
+                .infer_named(
+                    model_name,
+                    "",
+                    &spec.input_name,
+                    &spec.output_name,
+                    tensor,
+                    request_id,
+                )
+                .await?;
+            return Ok(output.into_flat());
+        }
+
+        let names = self.tensor_names(model_name).await?;
+        let result = self
+            .infer_named(
+                model_name,
+                "",
+                &names.input,
+                &names.output,
+                tensor,
+                request_id,
+            )
+            .await;
+
+        match result {
+            Ok((output, version)) => {
+                if !version.is_empty() && !names.version.is_empty() && version != names.version {
+                    info!(
+                        model_name,
+                        %version,
+                        "model version changed, rediscovering tensor names"
+                    );
+                    self.forget_tensor_names(model_name);
+                }
+                Ok(output.into_flat())
+            }
+            Err(err) => {
+                // The names may be stale after a model reload.
+                self.forget_tensor_names(model_name);
+                Err(err)
+            }
+        }
+    }
+
+    // Frames share one bidirectional stream instead of a call each. A frame
+    // Triton fails is reported in its slot and the stream carries on.
+    pub async fn infer_stream<S>(
+        &self,
+        tensors: S,
+    ) -> Result<impl Stream<Item = Result<Vec<f32>, TritonError>> + Send, TritonError>
+    where
+        S: Stream<Item = ImageTensor> + Send + 'static,
+    {
+        let client = self.clone();
+        let requests = tensors.map(move |tensor| {
+            let batch = tensor.shape.first().copied().unwrap_or(1);
+            let (input, raw_input) = client.build_input_tensor(&client.input_name, &tensor, batch);
+            ModelInferRequest {
+                model_name: client.model_name.clone(),
+                model_version: client.model_version.clone(),
+                id: String::new(),
+                parameters: HashMap::new(),
+                inputs: vec![input],
+                outputs: vec![client.build_requested_output(&client.output_name)],
+                raw_input_contents: raw_input.into_iter().collect(),
+            }
+        });
+
+        let responses = self
+            .client()
+            .await?
+            .model_stream_infer(requests)
+            .await
+            .map_err(|status| TritonError::Transport(status.to_string()))?
+            .into_inner();
+
+        let client = self.clone();
+        Ok(responses.map(move |message| {
+            let message = message.map_err(|status| TritonError::Transport(status.to_string()))?;
+            if !message.error_message.is_empty() {
+                return Err(TritonError::InvalidResponse(message.error_message));
+            }
+            let response = message.infer_response.ok_or_else(|| {
+                TritonError::InvalidResponse("stream message carries no response".into())
+            })?;
+            Ok(client
+                .extract_scores(response, &client.output_name)?
+                .into_flat())
+        }))
+    }
+
+    async fn tensor_names(&self, model_name: &str) -> Result<TensorNames, TritonError> {
+        if let Some(names) = self
+            .discovered_names
+            .read()
+            .expect("tensor name cache poisoned")
+            .get(model_name)
+        {
+            return Ok(names.clone());
+        }
+
+        let request = inference::ModelMetadataRequest {
+            name: model_name.to_string(),
+            version: String::new(),
+        };
+        let metadata = self
+            .client()
+            .await?
+            .model_metadata(request)
+            .await
+            .map_err(|status| TritonError::Transport(status.to_string()))?
+            .into_inner();
+
+        let first_name = |tensors: &[inference::model_metadata_response::TensorMetadata],
+                          kind: &str| {
+            tensors
+                .first()
+                .map(|tensor| tensor.name.clone())
+                .ok_or_else(|| {
+                    TritonError::InvalidResponse(format!(
+                        "model '{model_name}' reports no {kind} tensors"
+                    ))
+                })
+        };
+        let names = TensorNames {
+            input: first_name(&metadata.inputs, "input")?,
+            output: first_name(&metadata.outputs, "output")?,
+            version: metadata.versions.last().cloned().unwrap_or_default(),
+        };
+
+        self.discovered_names
+            .write()
+            .expect("tensor name cache poisoned")
+            .insert(model_name.to_string(), names.clone());
+        Ok(names)
+    }
+
+    fn forget_tensor_names(&self, model_name: &str) {
+        self.discovered_names
+            .write()
+            .expect("tensor name cache poisoned")
+            .remove(model_name);
+    }
+
+    async fn infer_named(
+        &self,
+        model_name: &str,
+        model_version: &str,
+        input_name: &str,
+        output_name: &str,
+        tensor: &ImageTensor,
+        request_id: &str,
+    ) -> Result<(InferOutput, String), TritonError> {
         if tensor.data.is_empty() {
             return Err(TritonError::InvalidResponse(
                 "tensor data cannot be empty".into(),
             ));
         }
 
-        let mut client_guard = self.channel.lock().await;
-        if client_guard.is_none() {
-            *client_guard = Some(self.connect().await?);
-        }
-        let client = client_guard.as_mut().expect("client must be initialized");
+        let batch = tensor.shape.first().copied().unwrap_or(1);
+        let padded_batch = self.padded_batch_size(batch);
 
+        let (input, raw_input) = self.build_input_tensor(input_name, tensor, padded_batch);
         let mut inputs = Vec::with_capacity(1);
-        inputs.push(self.build_input_tensor(tensor));
+        inputs.push(input);
 
         let mut outputs = Vec::with_capacity(1);
-        outputs.push(self.build_requested_output());
+        outputs.push(self.build_requested_output(output_name));
 
         let request = ModelInferRequest {
-            model_name: self.model_name.clone(),
-            model_version: String::new(),
-            id: String::new(),
+            model_name: model_name.to_string(),
+            model_version: model_version.to_string(),
+            id: request_id.to_string(),
             parameters: HashMap::new(),
             inputs,
             outputs,
-            raw_input_contents: Vec::new(),
+            raw_input_contents: raw_input.into_iter().collect(),
         };
 
-        let response = client
-            .model_infer(request)
-            .await
-            .map_err(|err| TritonError::Transport(err.to_string()))?
-            .into_inner();
+        let response = self.send(request).await?;
+        let version = response.model_version.clone();
 
-        self.extract_scores(response)
+        let output = self.extract_scores(response, output_name)?;
+        Ok((output.discard_padding(batch, padded_batch), version))
     }
 
-    fn build_input_tensor(&self, tensor: &ImageTensor) -> InferInputTensor {
-        let contents = InferTensorContents {
-            fp32_contents: tensor.data.clone(),
-            ..Default::default()
-        };
+    // Requests no particular outputs, which makes Triton return all of them.
+    pub async fn infer_outputs(
+        &self,
+        tensor: &ImageTensor,
+    ) -> Result<HashMap<String, Vec<f32>>, TritonError> {
+        if tensor.data.is_empty() {
+            return Err(TritonError::InvalidResponse(
+                "tensor data cannot be empty".into(),
+            ));
+        }
 
-        InferInputTensor {
-            name: self.input_name.clone(),
-            datatype: "FP32".to_string(),
-            shape: tensor.shape.clone(),
+        let batch = tensor.shape.first().copied().unwrap_or(1);
+        let padded_batch = self.padded_batch_size(batch);
+        let (input, raw_input) = self.build_input_tensor(&self.input_name, tensor, padded_batch);
+        let request = ModelInferRequest {
+            model_name: self.model_name.clone(),
+            model_version: self.model_version.clone(),
+            id: String::new(),
             parameters: HashMap::new(),
-            contents: Some(contents),
+            inputs: vec![input],
+            outputs: Vec::new(),
+            raw_input_contents: raw_input.into_iter().collect(),
+        };
+
+        let response = self.send(request).await?;
+        Ok(self
+            .extract_outputs(&response)?
+            .into_iter()
+            .map(|(name, scores)| (name, discard_padding(scores, batch, padded_batch)))
+            .collect())
+    }
+
+    // Healthy local endpoints are used exclusively; once none remain, every
+    // endpoint competes by weight so traffic fails over to the remote region.
+    fn select_endpoint(&self) -> &EndpointState {
+        if self.endpoints.len() == 1 {
+            return &self.endpoints[0];
         }
+
+        let healthy_local: Vec<&EndpointState> = self
+            .endpoints
+            .iter()
+            .filter(|state| state.local && state.health.is_healthy())
+            .collect();
+        let candidates = if healthy_local.is_empty() {
+            self.endpoints.iter().collect()
+        } else {
+            healthy_local
+        };
+
+        let weights: Vec<f64> = candidates
+            .iter()
+            .map(|state| state.health.weight(state.local))
+            .collect();
+        candidates[endpoints::select_weighted(&weights, rand::random::<f64>())]
     }
 
-    fn build_requested_output(&self) -> InferRequestedOutputTensor {
+    // Only failures to reach a serving endpoint are retried; a request Triton
+    // rejected would fail the same way again.
+    async fn send(
+        &self,
+        request: ModelInferRequest,
+    ) -> Result<inference::ModelInferResponse, TritonError> {
+        let mut attempt = 1;
+        loop {
+            if self.remaining() == Some(Duration::ZERO) {
+                return Err(TritonError::DeadlineExceeded);
+            }
+            match self.send_once(request.clone()).await {
+                Err(SendFailure {
+                    error,
+                    retryable: true,
+                }) if attempt < self.retry.max_attempts => {
+                    let delay = self.retry.delay(attempt);
+                    if self.remaining().is_some_and(|left| left <= delay) {
+                        return Err(TritonError::DeadlineExceeded);
+                    }
+                    warn!(attempt, ?delay, "retrying Triton inference: {error}");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result.map_err(|failure| failure.error),
+            }
+        }
+    }
+
+    async fn send_once(
+        &self,
+        request: ModelInferRequest,
+    ) -> Result<inference::ModelInferResponse, SendFailure> {
+        let state = self.select_endpoint();
+        let started = Instant::now();
+        let timeout = self.remaining();
+        let mut request = tonic::Request::new(request);
+        if let Some(timeout) = timeout {
+            request.set_timeout(timeout);
+        }
+
+        let result = match &state.handle {
+            ClientHandle::Eager(clients) => {
+                let mut client = clients[state.next_slot(clients.len())].clone();
+                within(timeout, client.model_infer(request)).await
+            }
+            ClientHandle::Lazy(slots) => {
+                let slot = &slots[state.next_slot(slots.len())];
+                let ca_modified = self.ca_certificate_check(state).await;
+                let mut client = {
+                    let mut client_guard = slot.lock().await;
+                    if let (Some(connection), Some(modified)) = (client_guard.as_ref(), ca_modified)
+                    {
+                        if connection.ca_modified != Some(modified) {
+                            info!("Triton CA certificate changed, rebuilding channel");
+                            *client_guard = None;
+                        }
+                    }
+                    if client_guard.is_none() {
+                        match self.connect(&state.endpoint).await {
+                            Ok(connection) => *client_guard = Some(connection),
+                            Err(error) => {
+                                state.health.record(false, started.elapsed());
+                                return Err(SendFailure {
+                                    error,
+                                    retryable: true,
+                                });
+                            }
+                        }
+                    }
+                    client_guard
+                        .as_ref()
+                        .expect("client must be initialized")
+                        .client
+                        .clone()
+                };
+
+                let result = within(timeout, client.model_infer(request)).await;
+                if matches!(&result, Err(status) if status.code() == Code::Unavailable) {
+                    *slot.lock().await = None;
+                }
+                result
+            }
+        };
+
+        match result {
+            Ok(response) => {
+                state.health.record(true, started.elapsed());
+                Ok(response.into_inner())
+            }
+            Err(status) => {
+                // Rejections of the request itself say nothing about the
+                // endpoint's health, and neither does a caller's short deadline.
+                let caller_deadline = timeout.is_some() && status.code() == Code::DeadlineExceeded;
+                let endpoint_fault = matches!(
+                    status.code(),
+                    Code::Unavailable | Code::DeadlineExceeded | Code::Unknown | Code::Internal
+                );
+                state
+                    .health
+                    .record(!endpoint_fault || caller_deadline, started.elapsed());
+                Err(SendFailure {
+                    error: if caller_deadline {
+                        TritonError::DeadlineExceeded
+                    } else {
+                        TritonError::Transport(status.to_string())
+                    },
+                    retryable: status.code() == Code::Unavailable,
+                })
+            }
+        }
+    }
+
+    // A connected client for auxiliary RPCs, sharing the inference channel.
+    async fn client(&self) -> Result<GrpcInferenceServiceClient<Channel>, TritonError> {
+        self.endpoint_client(self.select_endpoint()).await
+    }
+
+    async fn endpoint_client(
+        &self,
+        state: &EndpointState,
+    ) -> Result<GrpcInferenceServiceClient<Channel>, TritonError> {
+        match &state.handle {
+            ClientHandle::Eager(clients) => Ok(clients[state.next_slot(clients.len())].clone()),
+            ClientHandle::Lazy(slots) => {
+                let mut client_guard = slots[state.next_slot(slots.len())].lock().await;
+                if client_guard.is_none() {
+                    *client_guard = Some(self.connect(&state.endpoint).await?);
+                }
+                Ok(client_guard
+                    .as_ref()
+                    .expect("client must be initialized")
+                    .client
+                    .clone())
+            }
+        }
+    }
+
+    fn padded_batch_size(&self, batch: i64) -> i64 {
+        match self.pad_batch_to {
+            Some(size) if size as i64 > batch => size as i64,
+            _ => batch,
+        }
+    }
+
+    fn build_input_tensor(
+        &self,
+        input_name: &str,
+        tensor: &ImageTensor,
+        batch_size: i64,
+    ) -> (InferInputTensor, Option<Vec<u8>>) {
+        let mut data = tensor.data.clone();
+        let mut shape = tensor.shape.clone();
+        if let Some(batch) = shape.first_mut() {
+            if batch_size > *batch && *batch > 0 {
+                let per_item = data.len() / *batch as usize;
+                data.resize(per_item * batch_size as usize, 0.0);
+                *batch = batch_size;
+            }
+        }
+
+        let (contents, raw) = if self.input_dtype == InputDtype::Fp16 {
+            (None, Some(encode_raw_fp16(&data)))
+        } else if self.input_dtype == InputDtype::Uint8 {
+            (None, Some(encode_raw_uint8(&data)))
+        } else if self.raw_input {
+            (None, Some(encode_raw_fp32(&data)))
+        } else {
+            let contents = InferTensorContents {
+                fp32_contents: data,
+                ..Default::default()
+            };
+            (Some(contents), None)
+        };
+
+        let input = InferInputTensor {
+            name: input_name.to_string(),
+            datatype: self.input_dtype.as_str().to_string(),
+            shape,
+            parameters: HashMap::new(),
+            contents,
+        };
+        (input, raw)
+    }
+
+    fn build_requested_output(&self, output_name: &str) -> InferRequestedOutputTensor {
         let mut parameters = HashMap::new();
         parameters.insert(
             "binary_data".to_string(),
             InferParameter {
                 parameter_choice: Some(inference::infer_parameter::ParameterChoice::BoolParam(
-                    false,
+                    self.binary_output,
                 )),
             },
         );
 
         InferRequestedOutputTensor {
-            name: self.output_name.clone(),
+            name: output_name.to_string(),
             parameters,
         }
     }
 
-    async fn connect(&self) -> Result<GrpcInferenceServiceClient<Channel>, TritonError> {
+    // The CA file's current mtime, read at most once per interval and before
+    // any slot is locked. A failed stat (say, mid-rotation) reports nothing so
+    // a healthy channel is kept.
+    async fn ca_certificate_check(&self, state: &EndpointState) -> Option<SystemTime> {
+        if !self.use_tls || self.ca_certificate_path.is_none() {
+            return None;
+        }
+        {
+            let mut checked = state.ca_checked.lock().expect("CA check lock poisoned");
+            if checked.is_some_and(|at| at.elapsed() < CA_CHECK_INTERVAL) {
+                return None;
+            }
+            *checked = Some(Instant::now());
+        }
+        self.ca_certificate_modified().await
+    }
+
+    async fn ca_certificate_modified(&self) -> Option<SystemTime> {
+        let path = self.ca_certificate_path.as_ref()?;
+        tokio::fs::metadata(path).await.ok()?.modified().ok()
+    }
+
+    async fn connect(&self, endpoint: &str) -> Result<Connection, TritonError> {
         let tls_domain = if self.use_tls {
-            let uri = self
-                .endpoint
+            let uri = endpoint
                 .parse::<Uri>()
                 .map_err(|err| TritonError::Configuration(err.to_string()))?;
             Some(
@@ -147,22 +1376,48 @@ impl TritonClient {
             None
         };
 
-        let mut endpoint = Endpoint::from_shared(self.endpoint.clone())
+        let mut endpoint = Endpoint::from_shared(endpoint.to_string())
             .map_err(|err| TritonError::Configuration(err.to_string()))?
-            .connect_timeout(Duration::from_secs(5))
-            .timeout(Duration::from_secs(15));
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.request_timeout);
+        if let Some(keepalive) = self.keepalive {
+            endpoint = endpoint
+                .http2_keep_alive_interval(keepalive.interval)
+                .keep_alive_timeout(keepalive.timeout)
+                .keep_alive_while_idle(true);
+        }
 
+        let mut ca_modified = None;
         if self.use_tls {
             let mut tls = ClientTlsConfig::new();
             if let Some(domain) = tls_domain {
                 tls = tls.domain_name(domain);
             }
             if let Some(path) = &self.ca_certificate_path {
+                ca_modified = self.ca_certificate_modified().await;
                 let pem = tokio::fs::read(path)
                     .await
                     .map_err(|err| TritonError::Configuration(err.to_string()))?;
                 tls = tls.ca_certificate(Certificate::from_pem(pem));
             }
+            if let Some(identity) = &self.client_identity {
+                let certificate =
+                    tokio::fs::read(&identity.certificate_path)
+                        .await
+                        .map_err(|err| {
+                            TritonError::Configuration(format!(
+                                "failed to read client certificate {}: {err}",
+                                identity.certificate_path
+                            ))
+                        })?;
+                let key = tokio::fs::read(&identity.key_path).await.map_err(|err| {
+                    TritonError::Configuration(format!(
+                        "failed to read client key {}: {err}",
+                        identity.key_path
+                    ))
+                })?;
+                tls = tls.identity(Identity::from_pem(certificate, key));
+            }
             endpoint = endpoint
                 .tls_config(tls)
                 .map_err(|err| TritonError::Configuration(err.to_string()))?;
@@ -173,59 +1428,185 @@ impl TritonClient {
             .await
             .map_err(|err| TritonError::Transport(err.to_string()))?;
 
-        Ok(GrpcInferenceServiceClient::new(channel))
+        let client = GrpcInferenceServiceClient::new(channel);
+        #[cfg(feature = "gzip")]
+        let client = if self.compression {
+            client
+                .send_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Gzip)
+        } else {
+            client
+        };
+        if let Some(readiness) = &self.readiness {
+            wait_until_ready(client.clone(), readiness).await?;
+        }
+
+        Ok(Connection {
+            client,
+            ca_modified,
+        })
     }
 
     fn extract_scores(
         &self,
         response: inference::ModelInferResponse,
-    ) -> Result<Vec<f32>, TritonError> {
-        let mut scores = if let Some(output) = response
+        output_name: &str,
+    ) -> Result<InferOutput, TritonError> {
+        let shape = response
             .outputs
             .iter()
-            .find(|output| output.name == self.output_name)
-        {
-            if let Some(contents) = &output.contents {
-                if !contents.fp32_contents.is_empty() {
-                    contents.fp32_contents.clone()
-                } else {
-                    Vec::new()
-                }
-            } else {
-                Vec::new()
-            }
-        } else {
-            return Err(TritonError::InvalidResponse(format!(
-                "missing output tensor '{}' in response",
-                self.output_name
-            )));
-        };
+            .find(|output| output.name == output_name)
+            .map(|output| output.shape.clone())
+            .ok_or_else(|| {
+                TritonError::InvalidResponse(format!(
+                    "missing output tensor '{}' in response",
+                    output_name
+                ))
+            })?;
 
-        if !scores.is_empty() {
-            return Ok(scores);
+        let scores = self
+            .extract_outputs(&response)?
+            .remove(output_name)
+            .unwrap_or_default();
+        if scores.is_empty() {
+            return Err(TritonError::InvalidResponse(
+                "no output data found in Triton response".into(),
+            ));
         }
 
-        if let Some(raw_bytes) = response.raw_output_contents.first() {
-            if raw_bytes.len() % std::mem::size_of::<f32>() != 0 {
-                return Err(TritonError::InvalidResponse(
-                    "output tensor byte length is not a multiple of 4".into(),
-                ));
-            }
-            let element_count = raw_bytes.len() / std::mem::size_of::<f32>();
-            scores = (0..element_count)
-                .map(|index| {
-                    let start = index * 4;
-                    LittleEndian::read_f32(&raw_bytes[start..start + 4])
-                })
-                .collect();
+        InferOutput::from_shape(scores, &shape)
+    }
+
+    // raw_output_contents, when present, holds one entry per output in the
+    // same order as `outputs`, so an output's bytes are found by position.
+    // They take precedence when binary output was requested, and otherwise
+    // only fill in outputs that came without typed contents.
+    // Integer outputs such as class indices are widened to f32, which is exact
+    // up to 2^24; outputs of other datatypes are skipped.
+    pub fn extract_outputs(
+        &self,
+        response: &inference::ModelInferResponse,
+    ) -> Result<HashMap<String, Vec<f32>>, TritonError> {
+        let mut outputs = HashMap::with_capacity(response.outputs.len());
+        for (index, output) in response.outputs.iter().enumerate() {
+            let contents = output.contents.clone().unwrap_or_default();
+            let values: Vec<f32> = match output.datatype.as_str() {
+                "FP32" => contents.fp32_contents,
+                "INT32" => contents
+                    .int_contents
+                    .into_iter()
+                    .map(|v| v as f32)
+                    .collect(),
+                "INT64" => contents
+                    .int64_contents
+                    .into_iter()
+                    .map(|v| v as f32)
+                    .collect(),
+                _ => continue,
+            };
+            let values = match response.raw_output_contents.get(index) {
+                Some(raw_bytes) if self.binary_output || values.is_empty() => {
+                    decode_raw(raw_bytes, &output.datatype)?
+                }
+                _ => values,
+            };
+            outputs.insert(output.name.clone(), values);
         }
+        Ok(outputs)
+    }
+}
 
-        if scores.is_empty() {
-            return Err(TritonError::InvalidResponse(
-                "no FP32 data found in Triton response".into(),
-            ));
+// grpc-timeout only asks the server to give up, so the call is also dropped
+// locally once the caller's time is up.
+async fn within<T>(
+    timeout: Option<Duration>,
+    call: impl std::future::Future<Output = Result<T, tonic::Status>>,
+) -> Result<T, tonic::Status> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, call)
+            .await
+            .unwrap_or_else(|_| Err(tonic::Status::deadline_exceeded("caller deadline exceeded"))),
+        None => call.await,
+    }
+}
+
+fn discard_padding(mut scores: Vec<f32>, batch: i64, padded_batch: i64) -> Vec<f32> {
+    if padded_batch > batch && batch > 0 {
+        let per_item = scores.len() / padded_batch as usize;
+        scores.truncate(per_item * batch as usize);
+    }
+    scores
+}
+
+// Triton reads raw tensor bytes as little-endian regardless of the client's
+// host byte order, so never fall back to a native-endian transmute here.
+pub fn encode_raw_fp32(data: &[f32]) -> Vec<u8> {
+    let mut bytes = vec![0u8; data.len() * std::mem::size_of::<f32>()];
+    LittleEndian::write_f32_into(data, &mut bytes);
+    bytes
+}
+
+pub fn encode_raw_uint8(data: &[f32]) -> Vec<u8> {
+    data.iter()
+        .map(|&value| (value * 255.0).round().clamp(0.0, 255.0) as u8)
+        .collect()
+}
+
+fn decode_raw(bytes: &[u8], datatype: &str) -> Result<Vec<f32>, TritonError> {
+    let width = if datatype == "INT64" { 8 } else { 4 };
+    if bytes.len() % width != 0 {
+        return Err(TritonError::InvalidResponse(format!(
+            "{datatype} output tensor byte length is not a multiple of {width}"
+        )));
+    }
+    let values = match datatype {
+        "INT32" => bytes
+            .chunks_exact(width)
+            .map(|chunk| LittleEndian::read_i32(chunk) as f32)
+            .collect(),
+        "INT64" => bytes
+            .chunks_exact(width)
+            .map(|chunk| LittleEndian::read_i64(chunk) as f32)
+            .collect(),
+        _ => {
+            let mut values = vec![0.0; bytes.len() / width];
+            LittleEndian::read_f32_into(bytes, &mut values);
+            values
         }
+    };
+    Ok(values)
+}
+
+pub fn encode_raw_fp16(data: &[f32]) -> Vec<u8> {
+    data.iter()
+        .flat_map(|&value| f16::from_f32(value).to_le_bytes())
+        .collect()
+}
 
-        Ok(scores)
+async fn wait_until_ready(
+    mut client: GrpcInferenceServiceClient<Channel>,
+    policy: &RetryPolicy,
+) -> Result<(), TritonError> {
+    let mut last_error = None;
+    for check in 1..=policy.max_attempts {
+        match client.server_ready(inference::ServerReadyRequest {}).await {
+            Ok(response) if response.get_ref().ready => return Ok(()),
+            Ok(_) => last_error = None,
+            Err(status) => last_error = Some(status),
+        }
+        if check < policy.max_attempts {
+            tokio::time::sleep(policy.base_delay).await;
+        }
     }
+
+    Err(TritonError::Transport(match last_error {
+        Some(status) => format!(
+            "Triton readiness check failed after {} attempts: {status}",
+            policy.max_attempts
+        ),
+        None => format!(
+            "Triton server still not ready after {} checks",
+            policy.max_attempts
+        ),
+    }))
 }
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use tonic::Request;
+use tracing::info;
+
+pub const TENANT_HEADER: &str = "x-tenant-id";
+pub const UNKNOWN_TENANT: &str = "unknown";
+
+// Approximate cost of one request for chargeback: CPU spent on the blocking
+// preprocess path plus payload bytes in each direction.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ResourceUsage {
+    pub cpu_time: Duration,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+impl ResourceUsage {
+    pub fn emit(&self, tenant: &str, rpc: &'static str) {
+        info!(
+            target: "resource_usage",
+            tenant,
+            rpc,
+            cpu_ms = self.cpu_time.as_secs_f64() * 1000.0,
+            bytes_in = self.bytes_in,
+            bytes_out = self.bytes_out,
+            "request resource usage"
+        );
+    }
+}
+
+pub fn tenant<T>(request: &Request<T>) -> String {
+    request
+        .metadata()
+        .get(TENANT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|tenant| !tenant.is_empty())
+        .unwrap_or(UNKNOWN_TENANT)
+        .to_string()
+}
+
+// CPU consumed by the calling thread so far, so a delta taken on one blocking
+// worker excludes whatever other requests are doing concurrently.
+#[cfg(unix)]
+pub fn thread_cpu_time() -> Option<Duration> {
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `now` is a valid, writable timespec for the duration of the call.
+    let result = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut now) };
+    (result == 0).then_some(Duration::new(now.tv_sec as u64, now.tv_nsec as u32))
+}
+
+#[cfg(not(unix))]
+pub fn thread_cpu_time() -> Option<Duration> {
+    None
+}
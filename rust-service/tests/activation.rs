@@ -0,0 +1,41 @@
+use rust_service::activation::Activation;
+
+const LOGITS: [f32; 3] = [2.0, 0.0, -1.0];
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(expected) {
+        assert!((actual - expected).abs() < 1e-5, "{actual} != {expected}");
+    }
+}
+
+#[test]
+fn none_passes_logits_through() {
+    assert_eq!(Activation::None.apply(&LOGITS), LOGITS.to_vec());
+}
+
+#[test]
+fn sigmoid_maps_each_logit_independently() {
+    assert_close(
+        &Activation::Sigmoid.apply(&LOGITS),
+        &[0.880_797, 0.5, 0.268_941],
+    );
+}
+
+#[test]
+fn softmax_normalizes_across_the_whole_vector() {
+    let probabilities = Activation::Softmax.apply(&LOGITS);
+    assert_close(&probabilities, &[0.843_795, 0.114_195, 0.042_010]);
+    assert!((probabilities.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+
+    // Large logits must not overflow exp().
+    assert_close(&Activation::Softmax.apply(&[1000.0, 1000.0]), &[0.5, 0.5]);
+}
+
+#[test]
+fn activation_parses_case_insensitively() {
+    assert_eq!("Sigmoid".parse::<Activation>(), Ok(Activation::Sigmoid));
+    assert_eq!("softmax".parse::<Activation>(), Ok(Activation::Softmax));
+    assert_eq!("none".parse::<Activation>(), Ok(Activation::None));
+    assert!("relu".parse::<Activation>().is_err());
+}
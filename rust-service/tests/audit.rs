@@ -0,0 +1,95 @@
+use rust_service::{
+    audit::{AuditRecord, AuditSigner, AuditSink, JsonLinesAuditSink},
+    decision::Decision,
+};
+
+fn record() -> AuditRecord {
+    AuditRecord::new(
+        "req-1",
+        "user-42",
+        "0123456789abcdef",
+        0.87,
+        Decision::Accept,
+        1_700_000_000_000,
+    )
+}
+
+#[test]
+fn signed_records_verify_with_the_same_secret() {
+    let signer = AuditSigner::new("secret");
+    let signed = signer.sign(record());
+
+    assert_eq!(signed.hmac.len(), 64);
+    assert!(signer.verify(&signed));
+    assert!(!AuditSigner::new("other").verify(&signed));
+}
+
+#[test]
+fn signing_is_deterministic() {
+    let signer = AuditSigner::new("secret");
+    assert_eq!(signer.sign(record()), signer.sign(record()));
+}
+
+#[test]
+fn tampering_with_any_field_breaks_the_signature() {
+    let signer = AuditSigner::new("secret");
+    let signed = signer.sign(record());
+
+    let tampered = [
+        AuditRecord {
+            score: 0.88,
+            ..signed.clone()
+        },
+        AuditRecord {
+            decision: Decision::Review,
+            ..signed.clone()
+        },
+        AuditRecord {
+            request_id: "req-2".to_string(),
+            ..signed.clone()
+        },
+        AuditRecord {
+            timestamp_ms: signed.timestamp_ms + 1,
+            ..signed.clone()
+        },
+        AuditRecord {
+            hmac: "not-hex".to_string(),
+            ..signed.clone()
+        },
+    ];
+    for record in &tampered {
+        assert!(!signer.verify(record), "{record:?}");
+    }
+}
+
+#[test]
+fn user_id_is_stored_hashed() {
+    let record = record();
+    assert_ne!(record.user_id_hash, "user-42");
+    assert_eq!(
+        record.user_id_hash,
+        AuditRecord::new("other", "user-42", "", 0.0, Decision::Reject, 0).user_id_hash
+    );
+}
+
+#[test]
+fn json_lines_sink_appends_records() {
+    let path = std::env::temp_dir().join(format!("audit-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let signer = AuditSigner::new("secret");
+    {
+        let sink = JsonLinesAuditSink::open(&path).unwrap();
+        sink.persist(&signer.sign(record())).unwrap();
+        sink.persist(&signer.sign(record())).unwrap();
+    }
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let lines: Vec<serde_json::Value> = contents
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["decision"], "accept");
+    assert_eq!(lines[0]["request_id"], "req-1");
+}
@@ -0,0 +1,64 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rust_service::{
+    batch::verify_each,
+    verify::{verify_image_result::Outcome, VerifyRequest, VerifyResponse},
+};
+use tokio::time::{sleep, Duration};
+use tonic::{Code, Status};
+
+fn image(user_id: &str, image_data: &[u8]) -> VerifyRequest {
+    VerifyRequest {
+        user_id: user_id.to_string(),
+        image_data: image_data.to_vec(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn failed_items_are_reported_without_failing_the_batch() {
+    let images = vec![image("alice", b"\x89PNG"), image("bob", b"")];
+
+    let results = verify_each(images, 2, |image| async move {
+        if image.image_data.is_empty() {
+            return Err(Status::invalid_argument("image data cannot be empty"));
+        }
+        Ok(VerifyResponse {
+            success: true,
+            score: 0.9,
+            ..Default::default()
+        })
+    })
+    .await;
+
+    assert_eq!(results.len(), 2);
+    match &results[0].outcome {
+        Some(Outcome::Response(response)) => assert!(response.success),
+        other => panic!("expected a response, got {other:?}"),
+    }
+    match &results[1].outcome {
+        Some(Outcome::Error(error)) => {
+            assert_eq!(error.code, Code::InvalidArgument as i32);
+            assert_eq!(error.message, "image data cannot be empty");
+        }
+        other => panic!("expected an error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn concurrency_stays_within_the_limit() {
+    let (in_flight, peak) = (&AtomicUsize::new(0), &AtomicUsize::new(0));
+    let images = (0..8).map(|i| image(&format!("user-{i}"), b"x")).collect();
+
+    let results = verify_each(images, 3, |_| async move {
+        let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        peak.fetch_max(current, Ordering::SeqCst);
+        sleep(Duration::from_millis(10)).await;
+        in_flight.fetch_sub(1, Ordering::SeqCst);
+        Ok(VerifyResponse::default())
+    })
+    .await;
+
+    assert_eq!(results.len(), 8);
+    assert_eq!(peak.load(Ordering::SeqCst), 3);
+}
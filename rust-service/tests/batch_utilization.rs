@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use rust_service::triton_client::{
+    inference::{InferBatchStatistics, InferStatistics, ModelStatistics, StatisticDuration},
+    BatchUtilization,
+};
+
+fn executions(count: u64) -> Option<StatisticDuration> {
+    Some(StatisticDuration { count, ns: 0 })
+}
+
+#[test]
+fn summarizes_batch_and_queue_statistics() {
+    let stats = ModelStatistics {
+        name: "verifier".to_string(),
+        inference_stats: Some(InferStatistics {
+            queue: Some(StatisticDuration {
+                count: 4,
+                ns: 8_000_000,
+            }),
+            ..Default::default()
+        }),
+        batch_stats: vec![
+            InferBatchStatistics {
+                batch_size: 1,
+                compute_infer: executions(2),
+                ..Default::default()
+            },
+            InferBatchStatistics {
+                batch_size: 4,
+                compute_infer: executions(2),
+                ..Default::default()
+            },
+            InferBatchStatistics {
+                batch_size: 8,
+                compute_infer: executions(0),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let utilization = BatchUtilization::from_statistics(&stats);
+    assert_eq!(
+        utilization
+            .executions_by_batch_size
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec![(1, 2), (4, 2)]
+    );
+    assert_eq!(
+        utilization.average_queue_time,
+        Some(Duration::from_millis(2))
+    );
+}
+
+#[test]
+fn empty_statistics_have_no_averages() {
+    let utilization = BatchUtilization::from_statistics(&ModelStatistics::default());
+    assert_eq!(utilization.average_batch_size(), None);
+    assert_eq!(utilization.average_queue_time, None);
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "simd")]
+
+use image::{Rgb, RgbImage};
+use rust_service::image::{to_chw_tensor_chunked, to_chw_tensor_scalar};
+
+#[test]
+fn chunked_path_is_bit_identical_to_scalar() {
+    // Odd dimensions so no row lines up with any chunk boundary.
+    let image = RgbImage::from_fn(37, 19, |x, y| {
+        Rgb([(x * 7) as u8, (y * 13) as u8, (x * y) as u8])
+    });
+
+    let scalar = to_chw_tensor_scalar(&image);
+    let chunked = to_chw_tensor_chunked(&image);
+    assert_eq!(scalar.len(), chunked.len());
+    assert!(scalar
+        .iter()
+        .zip(&chunked)
+        .all(|(a, b)| a.to_bits() == b.to_bits()));
+}
+
+#[test]
+fn every_byte_value_normalizes_identically() {
+    let image = RgbImage::from_fn(256, 1, |x, _| Rgb([x as u8, 255 - x as u8, x as u8]));
+
+    let scalar: Vec<u32> = to_chw_tensor_scalar(&image)
+        .iter()
+        .map(|v| v.to_bits())
+        .collect();
+    let chunked: Vec<u32> = to_chw_tensor_chunked(&image)
+        .iter()
+        .map(|v| v.to_bits())
+        .collect();
+    assert_eq!(scalar, chunked);
+}
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use rust_service::deadline::{from_metadata, parse_grpc_timeout};
+use tonic::metadata::MetadataMap;
+
+#[test]
+fn grpc_timeout_units_are_parsed() {
+    assert_eq!(parse_grpc_timeout("2H"), Some(Duration::from_secs(7200)));
+    assert_eq!(parse_grpc_timeout("3M"), Some(Duration::from_secs(180)));
+    assert_eq!(parse_grpc_timeout("5S"), Some(Duration::from_secs(5)));
+    assert_eq!(parse_grpc_timeout("250m"), Some(Duration::from_millis(250)));
+    assert_eq!(
+        parse_grpc_timeout("99999999u"),
+        Some(Duration::from_micros(99_999_999))
+    );
+    assert_eq!(parse_grpc_timeout("10n"), Some(Duration::from_nanos(10)));
+}
+
+#[test]
+fn malformed_grpc_timeouts_are_ignored() {
+    for value in ["", "m", "100", "100x", "-1S", "123456789S", "1.5S", "10é"] {
+        assert_eq!(parse_grpc_timeout(value), None, "{value}");
+    }
+}
+
+#[test]
+fn deadline_is_read_from_request_metadata() {
+    let mut metadata = MetadataMap::new();
+    assert_eq!(from_metadata(&metadata), None);
+
+    metadata.insert("grpc-timeout", "1500m".parse().unwrap());
+    assert_eq!(from_metadata(&metadata), Some(Duration::from_millis(1500)));
+}
@@ -0,0 +1,96 @@
+use rust_service::decision::{Decision, DecisionTable, DecisionTableError, VerifyThreshold};
+
+const TABLE: &str = r#"{
+    "default": [
+        { "min": 0.0, "max": 0.5, "decision": "reject" },
+        { "min": 0.5, "max": 1.0, "decision": "accept" }
+    ],
+    "high_risk": [
+        { "min": 0.8, "max": 1.0, "decision": "accept" },
+        { "min": 0.0, "max": 0.6, "decision": "reject" },
+        { "min": 0.6, "max": 0.8, "decision": "review" }
+    ]
+}"#;
+
+#[test]
+fn decides_by_segment_with_default_fallback() {
+    let table = DecisionTable::from_json(TABLE).unwrap();
+
+    assert_eq!(table.decide("", 0.7), Some(Decision::Accept));
+    assert_eq!(table.decide("high_risk", 0.7), Some(Decision::Review));
+    assert_eq!(table.decide("high_risk", 0.59), Some(Decision::Reject));
+    assert_eq!(table.decide("high_risk", 1.0), Some(Decision::Accept));
+    assert_eq!(table.decide("unknown", 0.7), None);
+}
+
+#[test]
+fn non_finite_scores_get_no_decision() {
+    let table = DecisionTable::from_json(TABLE).unwrap();
+
+    for score in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        assert_eq!(table.decide("", score), None, "{score}");
+        assert_eq!(table.decide("high_risk", score), None, "{score}");
+    }
+}
+
+#[test]
+fn rejects_overlapping_ranges() {
+    let json = r#"{ "default": [
+        { "min": 0.0, "max": 0.6, "decision": "reject" },
+        { "min": 0.5, "max": 1.0, "decision": "accept" }
+    ] }"#;
+
+    assert!(matches!(
+        DecisionTable::from_json(json),
+        Err(DecisionTableError::InvalidSegment { .. })
+    ));
+}
+
+#[test]
+fn rejects_incomplete_coverage() {
+    let gap = r#"{ "default": [
+        { "min": 0.0, "max": 0.4, "decision": "reject" },
+        { "min": 0.5, "max": 1.0, "decision": "accept" }
+    ] }"#;
+    let short = r#"{ "default": [
+        { "min": 0.0, "max": 0.9, "decision": "reject" }
+    ] }"#;
+
+    assert!(DecisionTable::from_json(gap).is_err());
+    assert!(DecisionTable::from_json(short).is_err());
+}
+
+#[test]
+fn requires_default_segment() {
+    let json = r#"{ "vip": [ { "min": 0.0, "max": 1.0, "decision": "accept" } ] }"#;
+
+    assert!(matches!(
+        DecisionTable::from_json(json),
+        Err(DecisionTableError::MissingDefault)
+    ));
+}
+
+#[test]
+fn threshold_accepts_from_the_boundary_upwards() {
+    let threshold: VerifyThreshold = "0.62".parse().unwrap();
+
+    assert_eq!(threshold.decide(0.62), Decision::Accept);
+    assert_eq!(threshold.decide(0.6199), Decision::Reject);
+    assert_eq!(VerifyThreshold::default().decide(0.5), Decision::Accept);
+    assert_eq!(VerifyThreshold::default().decide(0.49), Decision::Reject);
+}
+
+#[test]
+fn threshold_rejects_non_finite_scores() {
+    for score in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        assert_eq!(VerifyThreshold::default().decide(score), Decision::Reject);
+    }
+}
+
+#[test]
+fn threshold_outside_unit_interval_is_rejected() {
+    assert!("1.2".parse::<VerifyThreshold>().is_err());
+    assert!("-0.1".parse::<VerifyThreshold>().is_err());
+    assert!("NaN".parse::<VerifyThreshold>().is_err());
+    assert!("high".parse::<VerifyThreshold>().is_err());
+}
@@ -0,0 +1,45 @@
+use std::io::Cursor;
+
+use image::{ImageOutputFormat, Rgb, RgbImage};
+use rust_service::{
+    dry_run,
+    image::{preprocess_with, PreprocessConfig, TensorLayout},
+    ImageTensor,
+};
+
+fn png(color: [u8; 3]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    RgbImage::from_pixel(64, 48, Rgb(color))
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .unwrap();
+    bytes
+}
+
+#[test]
+fn scores_are_the_first_channel_mean_of_the_preprocessed_image() {
+    for layout in [TensorLayout::Nchw, TensorLayout::Nhwc] {
+        let config = PreprocessConfig {
+            layout,
+            ..Default::default()
+        };
+        let tensor = preprocess_with(&png([204, 51, 0]), &config).unwrap();
+
+        let scores = dry_run::scores(&tensor, layout);
+        assert_eq!(scores.len(), 1);
+        assert!((scores[0] - 0.8).abs() < 1e-3, "{layout:?}: {scores:?}");
+        assert!((0.0..=1.0).contains(&scores[0]));
+        assert_eq!(scores, dry_run::scores(&tensor, layout));
+    }
+}
+
+#[test]
+fn each_batch_element_is_scored_separately() {
+    let tensor = ImageTensor {
+        shape: vec![2, 2, 1, 2],
+        data: vec![0.2, 0.4, 9.0, 9.0, 0.6, 1.0, 9.0, 9.0],
+    };
+    let scores = dry_run::scores(&tensor, TensorLayout::Nchw);
+    assert_eq!(scores.len(), 2);
+    assert!((scores[0] - 0.3).abs() < 1e-6);
+    assert!((scores[1] - 0.8).abs() < 1e-6);
+}
@@ -0,0 +1,79 @@
+use rust_service::embedding::{
+    cosine_similarity, drift, l2_normalize, quantize_i8, Projection, ProjectionError,
+};
+
+#[test]
+fn l2_normalize_produces_unit_vector() {
+    let normalized = l2_normalize(&[3.0, 4.0]);
+
+    assert_eq!(normalized, vec![0.6, 0.8]);
+}
+
+#[test]
+fn l2_normalize_leaves_zero_vector_untouched() {
+    assert_eq!(l2_normalize(&[0.0, 0.0]), vec![0.0, 0.0]);
+}
+
+#[test]
+fn quantize_maps_unit_range_to_int8() {
+    let quantized = quantize_i8(&[1.0, -1.0, 0.0, 0.5]);
+
+    assert_eq!(
+        quantized.iter().map(|&byte| byte as i8).collect::<Vec<_>>(),
+        vec![127, -127, 0, 64]
+    );
+}
+
+#[test]
+fn projection_reduces_and_renormalizes() {
+    let projection = Projection::from_json("[[1, 0, 0], [0, 0, 2]]").unwrap();
+    assert_eq!(projection.input_dim(), 3);
+    assert_eq!(projection.output_dim(), 2);
+
+    let reduced = projection.apply(&[3.0, 5.0, 2.0]).unwrap();
+    assert_eq!(reduced, vec![0.6, 0.8]);
+}
+
+#[test]
+fn projection_rejects_mismatched_dimensions() {
+    let projection = Projection::from_json("[[1, 0], [0, 1]]").unwrap();
+
+    assert!(matches!(
+        projection.apply(&[1.0, 0.0, 0.0]),
+        Err(ProjectionError::DimensionMismatch {
+            expected: 2,
+            actual: 3
+        })
+    ));
+}
+
+#[test]
+fn ragged_projection_matrix_is_invalid() {
+    assert!(matches!(
+        Projection::from_json("[[1, 0], [0]]"),
+        Err(ProjectionError::Invalid(_))
+    ));
+    assert!(Projection::from_json("[]").is_err());
+}
+
+#[test]
+fn drift_is_cosine_distance() {
+    assert_eq!(drift(&[1.0, 0.0], &[2.0, 0.0]), Some(0.0));
+    assert_eq!(drift(&[1.0, 0.0], &[0.0, 3.0]), Some(1.0));
+    assert_eq!(drift(&[1.0, 0.0], &[-1.0, 0.0]), Some(2.0));
+}
+
+#[test]
+fn drift_is_undefined_for_mismatched_or_zero_vectors() {
+    assert_eq!(drift(&[1.0, 0.0], &[1.0, 0.0, 0.0]), None);
+    assert_eq!(drift(&[1.0, 0.0], &[0.0, 0.0]), None);
+    assert_eq!(cosine_similarity(&[], &[]), None);
+}
+
+#[test]
+fn cosine_similarity_matches_hand_computed_value() {
+    // dot = 4, |a| = 3, |b| = sqrt(5)
+    let similarity = cosine_similarity(&[1.0, 2.0, 2.0], &[2.0, 0.0, 1.0]).unwrap();
+
+    assert!((similarity - 0.596_284).abs() < 1e-6);
+}
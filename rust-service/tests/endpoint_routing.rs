@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use rust_service::endpoints::{select_weighted, EndpointHealth};
+
+#[test]
+fn weighted_selection_follows_cumulative_weights() {
+    let weights = [1.0, 3.0];
+
+    assert_eq!(select_weighted(&weights, 0.0), 0);
+    assert_eq!(select_weighted(&weights, 0.24), 0);
+    assert_eq!(select_weighted(&weights, 0.26), 1);
+    assert_eq!(select_weighted(&weights, 0.99), 1);
+}
+
+#[test]
+fn failures_degrade_health_and_successes_restore_it() {
+    let health = EndpointHealth::default();
+    assert!(health.is_healthy());
+
+    for _ in 0..5 {
+        health.record(false, Duration::from_millis(10));
+    }
+    assert!(!health.is_healthy());
+    assert!(health.success_rate() < 0.5);
+
+    for _ in 0..20 {
+        health.record(true, Duration::from_millis(10));
+    }
+    assert!(health.is_healthy());
+}
+
+#[test]
+fn local_endpoints_outweigh_equally_healthy_remote_ones() {
+    let local = EndpointHealth::default();
+    let remote = EndpointHealth::default();
+    local.record(true, Duration::from_millis(20));
+    remote.record(true, Duration::from_millis(20));
+
+    assert!(local.weight(true) > 5.0 * remote.weight(false));
+}
+
+#[test]
+fn slower_endpoints_receive_less_weight() {
+    let fast = EndpointHealth::default();
+    let slow = EndpointHealth::default();
+    fast.record(true, Duration::from_millis(10));
+    slow.record(true, Duration::from_millis(100));
+
+    assert!(fast.weight(false) > slow.weight(false));
+    assert_eq!(fast.latency(), Some(Duration::from_millis(10)));
+}
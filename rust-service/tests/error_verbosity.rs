@@ -0,0 +1,106 @@
+use rust_service::{
+    errors::{ErrorVerbosity, FieldViolations, ERROR_DOMAIN},
+    triton_client::TritonError,
+};
+use tonic::Code;
+use tonic_types::StatusExt;
+
+#[test]
+fn public_mode_hides_internal_detail() {
+    let status = ErrorVerbosity::Public.fail(
+        Code::Internal,
+        "inference failed",
+        "transport error: connection refused (triton:8001)",
+    );
+
+    assert_eq!(status.code(), Code::Internal);
+    assert_eq!(status.message(), "inference failed");
+}
+
+#[test]
+fn debug_mode_passes_detail_through() {
+    let status = ErrorVerbosity::Debug.fail(Code::Internal, "inference failed", "timeout");
+
+    assert_eq!(status.message(), "inference failed: timeout");
+}
+
+#[test]
+fn defaults_to_public() {
+    assert_eq!(ErrorVerbosity::default(), ErrorVerbosity::Public);
+}
+
+#[test]
+fn field_violations_are_reported_together() {
+    let mut violations = FieldViolations::default();
+    violations.add("image_data", "image data cannot be empty");
+    violations.add("user_id", "user_id is required");
+
+    let status = violations.into_result().unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(
+        status.message(),
+        "image data cannot be empty; user_id is required"
+    );
+
+    let fields: Vec<_> = status
+        .get_details_bad_request()
+        .unwrap()
+        .field_violations
+        .into_iter()
+        .map(|violation| violation.field)
+        .collect();
+    assert_eq!(fields, vec!["image_data", "user_id"]);
+}
+
+#[test]
+fn no_violations_is_ok() {
+    assert!(FieldViolations::default().into_result().is_ok());
+}
+
+#[test]
+fn oversized_payloads_are_invalid_arguments() {
+    let mut violations = FieldViolations::default();
+    violations.check_size("image_data", 1024, 1024);
+    assert!(violations.into_result().is_ok());
+
+    let mut violations = FieldViolations::default();
+    violations.check_size("image_data", 1025, 1024);
+    let status = violations.into_result().unwrap_err();
+
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(
+        status.message(),
+        "image_data is 1025 bytes, exceeding the 1024 byte limit"
+    );
+}
+
+#[test]
+fn triton_errors_map_to_status_codes_with_error_info() {
+    let cases = [
+        (
+            TritonError::Transport("deadline has elapsed".into()),
+            Code::Unavailable,
+            "TRITON_UNAVAILABLE",
+        ),
+        (
+            TritonError::Configuration("model 'x' has no input tensor 'input'".into()),
+            Code::FailedPrecondition,
+            "TRITON_MISCONFIGURED",
+        ),
+        (
+            TritonError::InvalidResponse("no output data found in Triton response".into()),
+            Code::Internal,
+            "TRITON_INVALID_RESPONSE",
+        ),
+    ];
+
+    for (err, code, reason) in cases {
+        let status = ErrorVerbosity::Public.fail_with(err.error_code(), "inference failed", &err);
+        assert_eq!(status.code(), code);
+        assert_eq!(status.message(), "inference failed");
+
+        let info = status.get_details_error_info().unwrap();
+        assert_eq!(info.reason, reason);
+        assert_eq!(info.domain, ERROR_DOMAIN);
+    }
+}
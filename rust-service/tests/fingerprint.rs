@@ -0,0 +1,227 @@
+use rust_service::{
+    activation::Activation,
+    embedding::Projection,
+    face::FaceDetector,
+    fingerprint::{config_fingerprint, model_fingerprint, template_fingerprint},
+    image::{InputSize, PreprocessConfig, TruncationPolicy},
+    triton_client::{InputDtype, TritonClient},
+};
+
+fn client(model_name: &str) -> TritonClient {
+    TritonClient::new(
+        "http://127.0.0.1:8001",
+        model_name,
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+}
+
+#[test]
+fn fingerprint_is_deterministic() {
+    let preprocess = PreprocessConfig::default();
+    let first = config_fingerprint(
+        &client("face_verification"),
+        &preprocess,
+        Activation::None,
+        None,
+    );
+    let second = config_fingerprint(
+        &client("face_verification"),
+        &preprocess,
+        Activation::None,
+        None,
+    );
+
+    assert_eq!(first, second);
+    assert_eq!(first.len(), 16);
+}
+
+#[test]
+fn fingerprint_changes_with_model_or_preprocessing() {
+    let preprocess = PreprocessConfig::default();
+    let baseline = config_fingerprint(
+        &client("face_verification"),
+        &preprocess,
+        Activation::None,
+        None,
+    );
+
+    let other_model = config_fingerprint(
+        &client("face_verification_v2"),
+        &preprocess,
+        Activation::None,
+        None,
+    );
+    assert_ne!(baseline, other_model);
+
+    let lenient = PreprocessConfig {
+        truncation: TruncationPolicy::Lenient,
+        ..Default::default()
+    };
+    let other_preprocess = config_fingerprint(
+        &client("face_verification"),
+        &lenient,
+        Activation::None,
+        None,
+    );
+    assert_ne!(baseline, other_preprocess);
+}
+
+#[test]
+fn fingerprint_changes_with_model_version() {
+    let preprocess = PreprocessConfig::default();
+    let latest = config_fingerprint(
+        &client("face_verification"),
+        &preprocess,
+        Activation::None,
+        None,
+    );
+    let pinned = config_fingerprint(
+        &client("face_verification").with_model_version("3"),
+        &preprocess,
+        Activation::None,
+        None,
+    );
+
+    assert_ne!(latest, pinned);
+}
+
+#[test]
+fn per_request_model_and_version_change_the_fingerprint() {
+    let preprocess = PreprocessConfig::default();
+    let triton = client("face_verification").with_model_version("3");
+    let configured = config_fingerprint(&triton, &preprocess, Activation::None, None);
+
+    assert_eq!(
+        model_fingerprint(
+            &triton,
+            &preprocess,
+            Activation::None,
+            None,
+            "face_verification",
+            "3"
+        ),
+        configured
+    );
+    assert_ne!(
+        model_fingerprint(
+            &triton,
+            &preprocess,
+            Activation::None,
+            None,
+            "face_verification",
+            "4"
+        ),
+        configured
+    );
+    assert_ne!(
+        model_fingerprint(
+            &triton,
+            &preprocess,
+            Activation::None,
+            None,
+            "face_verification_v2",
+            ""
+        ),
+        configured
+    );
+}
+
+#[test]
+fn fingerprint_changes_with_score_activation() {
+    let preprocess = PreprocessConfig::default();
+    let triton = client("face_verification");
+    let raw = config_fingerprint(&triton, &preprocess, Activation::None, None);
+
+    assert_ne!(
+        config_fingerprint(&triton, &preprocess, Activation::Sigmoid, None),
+        raw
+    );
+    assert_ne!(
+        config_fingerprint(&triton, &preprocess, Activation::Softmax, None),
+        raw
+    );
+}
+
+#[test]
+fn template_fingerprint_changes_with_the_projection() {
+    let configured = config_fingerprint(
+        &client("face_verification"),
+        &PreprocessConfig::default(),
+        Activation::None,
+        None,
+    );
+    let unprojected = template_fingerprint(&configured, None);
+    let projection = Projection::from_json("[[1, 0], [0, 1]]").unwrap();
+    let projected = template_fingerprint(&configured, Some(&projection));
+    let other = Projection::from_json("[[0, 1], [1, 0]]").unwrap();
+
+    assert_ne!(unprojected, configured);
+    assert_ne!(projected, unprojected);
+    assert_ne!(template_fingerprint(&configured, Some(&other)), projected);
+}
+
+#[test]
+fn fingerprint_changes_with_input_dtype() {
+    let preprocess = PreprocessConfig::default();
+    let fp32 = config_fingerprint(
+        &client("face_verification"),
+        &preprocess,
+        Activation::None,
+        None,
+    );
+
+    for dtype in [InputDtype::Fp16, InputDtype::Uint8] {
+        let triton = client("face_verification").with_input_dtype(dtype);
+        assert_ne!(
+            config_fingerprint(&triton, &preprocess, Activation::None, None),
+            fp32,
+            "{dtype:?}"
+        );
+    }
+}
+
+#[test]
+fn fingerprint_changes_with_the_face_detector() {
+    let preprocess = PreprocessConfig::default();
+    let triton = client("face_verification");
+    let undetected = config_fingerprint(&triton, &preprocess, Activation::None, None);
+    let detector = || {
+        FaceDetector::new(
+            triton.for_model("face_detector", "input", "boxes"),
+            InputSize {
+                width: 320,
+                height: 320,
+            },
+        )
+    };
+    let detected = config_fingerprint(&triton, &preprocess, Activation::None, Some(&detector()));
+    assert_ne!(detected, undetected);
+
+    let variants = [
+        detector().with_min_confidence(0.7),
+        detector().with_margin(0.4),
+        FaceDetector::new(
+            triton.for_model("face_detector", "input", "boxes"),
+            InputSize {
+                width: 640,
+                height: 640,
+            },
+        ),
+        FaceDetector::new(
+            triton.for_model("face_detector_v2", "input", "boxes"),
+            InputSize {
+                width: 320,
+                height: 320,
+            },
+        ),
+    ];
+    for variant in &variants {
+        assert_ne!(
+            config_fingerprint(&triton, &preprocess, Activation::None, Some(variant)),
+            detected
+        );
+    }
+}
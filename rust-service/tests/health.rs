@@ -0,0 +1,48 @@
+use std::{net::SocketAddr, time::Duration};
+
+use rust_service::health::{self, SERVICE_NAME};
+use tokio::{sync::oneshot, time};
+use tonic::transport::Server;
+use tonic_health::pb::{
+    health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn health_check_follows_triton_reachability() {
+    let addr: SocketAddr = "127.0.0.1:50080".parse().unwrap();
+    let (mut reporter, service) = tonic_health::server::health_reporter();
+    health::report_triton(&mut reporter, true).await;
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        Server::builder()
+            .add_service(service)
+            .serve_with_shutdown(addr, async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+            .unwrap();
+    });
+    time::sleep(Duration::from_millis(50)).await;
+
+    let mut client = HealthClient::connect(format!("http://{addr}"))
+        .await
+        .unwrap();
+    let check = |service: &str| {
+        let request = HealthCheckRequest {
+            service: service.to_string(),
+        };
+        let mut client = client.clone();
+        async move { client.check(request).await.unwrap().into_inner().status }
+    };
+
+    assert_eq!(check(SERVICE_NAME).await, ServingStatus::Serving as i32);
+    assert_eq!(check("").await, ServingStatus::Serving as i32);
+
+    health::report_triton(&mut reporter, false).await;
+    assert_eq!(check(SERVICE_NAME).await, ServingStatus::NotServing as i32);
+    drop(client);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
@@ -0,0 +1,248 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use image::{ImageOutputFormat, Rgb, RgbImage};
+use rust_service::{
+    fetch::{FetchError, Fetched, ImageFetcher},
+    image::{preprocess_with, PreprocessConfig},
+    ImageTensor,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+use tonic::Code;
+
+const ETAG: &str = "\"v1\"";
+const BODY: &[u8] = b"image-bytes";
+
+// Minimal HTTP/1.1 origin that honours If-None-Match and counts full downloads.
+async fn spawn_origin() -> (SocketAddr, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let downloads = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&downloads);
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                let read = socket.read(&mut buf).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..read]);
+            }
+            let request = String::from_utf8_lossy(&request).to_ascii_lowercase();
+
+            let response = if request.contains(&format!("if-none-match: {ETAG}")) {
+                "HTTP/1.1 304 Not Modified\r\nconnection: close\r\n\r\n"
+                    .as_bytes()
+                    .to_vec()
+            } else {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut response = format!(
+                    "HTTP/1.1 200 OK\r\netag: {ETAG}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    BODY.len()
+                )
+                .into_bytes();
+                response.extend_from_slice(BODY);
+                response
+            };
+            socket.write_all(&response).await.unwrap();
+        }
+    });
+
+    (addr, downloads)
+}
+
+// Remote fetches are refused unless the origin's host is allowed, and the
+// test origin only speaks plain HTTP.
+fn local_fetcher(capacity: usize) -> ImageFetcher<ImageTensor> {
+    ImageFetcher::new(Duration::from_secs(5), capacity)
+        .unwrap()
+        .with_allowed_hosts(["127.0.0.1"])
+        .with_plain_http(true)
+}
+
+fn tensor() -> Arc<ImageTensor> {
+    Arc::new(ImageTensor {
+        shape: vec![1, 3, 1, 1],
+        data: vec![0.1, 0.2, 0.3],
+    })
+}
+
+#[tokio::test]
+async fn unchanged_images_are_served_from_the_cache() {
+    let (addr, downloads) = spawn_origin().await;
+    let url = format!("http://{addr}/face.png");
+    let fetcher = local_fetcher(8);
+
+    let Fetched::Fresh { bytes, validators } = fetcher.fetch(&url, "default").await.unwrap() else {
+        panic!("first fetch must download the image");
+    };
+    assert_eq!(bytes, BODY);
+    assert_eq!(validators.etag.as_deref(), Some(ETAG));
+    fetcher.store(&url, "default", validators, tensor());
+
+    let Fetched::Cached(cached) = fetcher.fetch(&url, "default").await.unwrap() else {
+        panic!("revalidated fetch must reuse the cached tensor");
+    };
+    assert_eq!(cached.data, vec![0.1, 0.2, 0.3]);
+    assert_eq!(downloads.load(Ordering::SeqCst), 1);
+
+    // Another variant of the same URL has its own entry.
+    assert!(matches!(
+        fetcher.fetch(&url, "rotated").await.unwrap(),
+        Fetched::Fresh { .. }
+    ));
+    assert_eq!(downloads.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn zero_capacity_disables_caching() {
+    let (addr, downloads) = spawn_origin().await;
+    let url = format!("http://{addr}/face.png");
+    let fetcher = local_fetcher(0);
+
+    for _ in 0..2 {
+        let Fetched::Fresh { validators, .. } = fetcher.fetch(&url, "default").await.unwrap()
+        else {
+            panic!("uncached fetch must download the image");
+        };
+        fetcher.store(&url, "default", validators, tensor());
+    }
+    assert_eq!(downloads.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn unsupported_schemes_are_rejected() {
+    let fetcher = ImageFetcher::<ImageTensor>::new(Duration::from_secs(5), 8).unwrap();
+    let result = fetcher.fetch("ftp://example.com/face.png", "default").await;
+    assert!(matches!(result, Err(FetchError::UnsupportedScheme(_))));
+}
+
+#[tokio::test]
+async fn hosts_off_the_allowlist_are_rejected() {
+    let (addr, downloads) = spawn_origin().await;
+    let url = format!("http://{addr}/face.png");
+
+    let unconfigured = ImageFetcher::<ImageTensor>::new(Duration::from_secs(5), 0).unwrap();
+    let other_host = ImageFetcher::<ImageTensor>::new(Duration::from_secs(5), 0)
+        .unwrap()
+        .with_allowed_hosts(["images.example.com"])
+        .with_plain_http(true);
+    for fetcher in [unconfigured, other_host] {
+        let err = match fetcher.fetch(&url, "default").await {
+            Err(err) => err,
+            Ok(_) => panic!("{url} must be refused"),
+        };
+        assert!(matches!(err, FetchError::HostNotAllowed(_)), "{err}");
+        assert_eq!(err.status_code(), Code::InvalidArgument);
+    }
+    assert_eq!(downloads.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn plain_http_needs_to_be_allowed() {
+    let (addr, downloads) = spawn_origin().await;
+    let fetcher = ImageFetcher::<ImageTensor>::new(Duration::from_secs(5), 0)
+        .unwrap()
+        .with_allowed_hosts(["127.0.0.1"]);
+
+    let result = fetcher
+        .fetch(&format!("http://{addr}/face.png"), "default")
+        .await;
+    assert!(matches!(result, Err(FetchError::UnsupportedScheme(_))));
+    assert_eq!(downloads.load(Ordering::SeqCst), 0);
+}
+
+fn file_root(name: &str) -> std::path::PathBuf {
+    let root = std::env::temp_dir().join(format!("image-fetch-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    root
+}
+
+#[tokio::test]
+async fn file_uris_beneath_the_root_are_loaded() {
+    let root = file_root("load");
+    let mut png = Vec::new();
+    RgbImage::from_pixel(40, 30, Rgb([200, 10, 10]))
+        .write_to(&mut std::io::Cursor::new(&mut png), ImageOutputFormat::Png)
+        .unwrap();
+    let path = root.join("face.png");
+    std::fs::write(&path, &png).unwrap();
+
+    let fetcher = ImageFetcher::<ImageTensor>::new(Duration::from_secs(5), 8)
+        .unwrap()
+        .with_file_root(&root)
+        .unwrap();
+    let uri = format!("file://{}", path.display());
+    let Fetched::Fresh { bytes, validators } = fetcher.fetch(&uri, "default").await.unwrap() else {
+        panic!("file fetch must return the file contents");
+    };
+    assert_eq!(bytes, png);
+    assert_eq!(validators, Default::default());
+
+    let config = PreprocessConfig::default();
+    let tensor = preprocess_with(&bytes, &config).unwrap();
+    assert_eq!(tensor.shape, config.tensor_shape());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn file_uris_need_a_root_and_stay_inside_it() {
+    let root = file_root("confine");
+    let outside = file_root("outside").join("secret.png");
+    std::fs::write(&outside, b"secret").unwrap();
+    let uri = format!("file://{}", outside.display());
+
+    let unconfigured = ImageFetcher::<ImageTensor>::new(Duration::from_secs(5), 8).unwrap();
+    assert!(matches!(
+        unconfigured.fetch(&uri, "default").await,
+        Err(FetchError::UnsupportedScheme(_))
+    ));
+
+    let fetcher = ImageFetcher::<ImageTensor>::new(Duration::from_secs(5), 8)
+        .unwrap()
+        .with_file_root(&root)
+        .unwrap();
+    assert!(matches!(
+        fetcher.fetch(&uri, "default").await,
+        Err(FetchError::OutsideRoot(_))
+    ));
+    let escape = format!(
+        "file://{}/../{}",
+        root.display(),
+        outside
+            .strip_prefix(std::env::temp_dir())
+            .unwrap()
+            .display()
+    );
+    assert!(matches!(
+        fetcher.fetch(&escape, "default").await,
+        Err(FetchError::OutsideRoot(_))
+    ));
+
+    std::fs::remove_dir_all(&root).unwrap();
+    std::fs::remove_dir_all(outside.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn oversized_images_are_rejected() {
+    let (addr, _) = spawn_origin().await;
+    let fetcher = local_fetcher(0).with_max_bytes(BODY.len() - 1);
+    let result = fetcher
+        .fetch(&format!("http://{addr}/face.png"), "default")
+        .await;
+    assert!(matches!(result, Err(FetchError::TooLarge { .. })));
+}
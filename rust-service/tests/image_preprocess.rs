@@ -0,0 +1,750 @@
+use std::io::Cursor;
+
+use image::{
+    codecs::gif::GifEncoder, DynamicImage, Frame, ImageBuffer, ImageFormat, ImageOutputFormat,
+    Luma, LumaA, Rgb, RgbImage, Rgba, RgbaImage,
+};
+use rust_service::{
+    image::{
+        center_crop, decode, estimate_memory, preprocess_batch, preprocess_regions,
+        preprocess_with, preprocess_with_meta, AllowedFormats, ChannelMode, ChannelOrder,
+        CropRegion, ImageError, InputSize, Normalization, PreprocessConfig, ResizeFilter,
+        ResizeMode, Rotation, TensorLayout, TruncationPolicy,
+    },
+    quality::{QualityGates, QualityRejection},
+};
+
+fn encode(image: RgbImage, format: ImageOutputFormat) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgb8(image)
+        .write_to(&mut Cursor::new(&mut bytes), format)
+        .unwrap();
+    bytes
+}
+
+fn solid(width: u32, height: u32, color: [u8; 3]) -> RgbImage {
+    RgbImage::from_pixel(width, height, Rgb(color))
+}
+
+#[test]
+fn strict_mode_rejects_truncated_png() {
+    let mut bytes = encode(solid(32, 32, [200, 10, 10]), ImageOutputFormat::Png);
+    bytes.truncate(bytes.len() - 4);
+
+    let result = preprocess_with(&bytes, &PreprocessConfig::default());
+    assert!(matches!(result, Err(ImageError::Truncated(_))));
+}
+
+#[test]
+fn strict_mode_accepts_complete_images() {
+    let config = PreprocessConfig::default();
+    for format in [ImageOutputFormat::Png, ImageOutputFormat::Jpeg(90)] {
+        let bytes = encode(solid(32, 32, [200, 10, 10]), format);
+        assert!(preprocess_with(&bytes, &config).is_ok());
+    }
+}
+
+#[test]
+fn lenient_mode_skips_end_marker_check() {
+    let mut bytes = encode(solid(32, 32, [200, 10, 10]), ImageOutputFormat::Jpeg(90));
+    bytes.truncate(bytes.len() - 2);
+
+    let strict = preprocess_with(&bytes, &PreprocessConfig::default());
+    assert!(matches!(strict, Err(ImageError::Truncated(_))));
+
+    let config = PreprocessConfig {
+        truncation: TruncationPolicy::Lenient,
+        ..Default::default()
+    };
+    let lenient = preprocess_with(&bytes, &config);
+    assert!(!matches!(lenient, Err(ImageError::Truncated(_))));
+}
+
+#[test]
+fn memory_budget_rejects_before_decoding() {
+    let bytes = encode(solid(256, 256, [10, 10, 10]), ImageOutputFormat::Png);
+    let config = PreprocessConfig {
+        memory_budget: Some(256 * 256),
+        ..Default::default()
+    };
+
+    let result = preprocess_with(&bytes, &config);
+    assert!(matches!(
+        result,
+        Err(ImageError::MemoryBudgetExceeded { budget, .. }) if budget == 256 * 256
+    ));
+
+    let generous = PreprocessConfig {
+        memory_budget: Some(16 * 1024 * 1024),
+        ..Default::default()
+    };
+    assert!(preprocess_with(&bytes, &generous).is_ok());
+}
+
+#[test]
+fn memory_estimate_follows_the_source_bit_depth() {
+    let size = InputSize::default();
+    let tensor_bytes = 3 * 224 * 224 * 4;
+    let eight_bit = encode(solid(100, 50, [10, 10, 10]), ImageOutputFormat::Png);
+    let mut sixteen_bit = Vec::new();
+    DynamicImage::ImageLumaA16(ImageBuffer::from_pixel(100, 50, LumaA([1000u16, 65535])))
+        .write_to(&mut Cursor::new(&mut sixteen_bit), ImageOutputFormat::Png)
+        .unwrap();
+
+    assert_eq!(
+        estimate_memory(&eight_bit, size).unwrap(),
+        100 * 50 * 4 + tensor_bytes
+    );
+    assert_eq!(
+        estimate_memory(&sixteen_bit, size).unwrap(),
+        100 * 50 * 8 + tensor_bytes
+    );
+}
+
+#[test]
+fn regions_are_batched_in_configured_order() {
+    let mut image = solid(200, 100, [0, 0, 0]);
+    for y in 0..100 {
+        for x in 100..200 {
+            image.put_pixel(x, y, Rgb([255, 255, 255]));
+        }
+    }
+    let bytes = encode(image, ImageOutputFormat::Png);
+    let regions = vec![
+        CropRegion {
+            name: "main".to_string(),
+            x: 0.0,
+            y: 0.0,
+            width: 0.5,
+            height: 1.0,
+        },
+        CropRegion {
+            name: "ghost".to_string(),
+            x: 0.5,
+            y: 0.0,
+            width: 0.5,
+            height: 1.0,
+        },
+    ];
+
+    let tensor = preprocess_regions(&bytes, &PreprocessConfig::default(), &regions).unwrap();
+    let per_region = 3 * 224 * 224;
+    assert_eq!(tensor.shape, vec![2, 3, 224, 224]);
+    assert_eq!(tensor.data.len(), 2 * per_region);
+    assert!(tensor.data[..per_region].iter().all(|&value| value == 0.0));
+    assert!(tensor.data[per_region..].iter().all(|&value| value == 1.0));
+}
+
+#[test]
+fn regions_outside_the_image_are_rejected() {
+    let bytes = encode(solid(64, 64, [0, 0, 0]), ImageOutputFormat::Png);
+    let regions = vec![CropRegion {
+        name: "offscreen".to_string(),
+        x: 1.2,
+        y: 0.0,
+        width: 0.5,
+        height: 0.5,
+    }];
+
+    let result = preprocess_regions(&bytes, &PreprocessConfig::default(), &regions);
+    assert!(matches!(result, Err(ImageError::EmptyRegion(name)) if name == "offscreen"));
+}
+
+#[test]
+fn rotation_is_applied_before_resize() {
+    let mut image = solid(200, 100, [0, 0, 0]);
+    for y in 0..100 {
+        for x in 100..200 {
+            image.put_pixel(x, y, Rgb([255, 255, 255]));
+        }
+    }
+    let bytes = encode(image, ImageOutputFormat::Png);
+    let config = PreprocessConfig {
+        rotation: Rotation::Clockwise90,
+        ..Default::default()
+    };
+
+    let tensor = preprocess_with(&bytes, &config).unwrap();
+    // Rotating clockwise moves the black left half to the top.
+    assert_eq!(tensor.data[10 * 224 + 112], 0.0);
+    assert_eq!(tensor.data[214 * 224 + 112], 1.0);
+}
+
+#[test]
+fn resize_filter_changes_the_resampled_tensor() {
+    // Fine stripes alias differently under each filter when shrunk.
+    let image = RgbImage::from_fn(301, 301, |x, y| {
+        let value = if (x + 2 * y) % 3 == 0 { 255 } else { 0 };
+        Rgb([value, value, value])
+    });
+    let bytes = encode(image, ImageOutputFormat::Png);
+    let tensor = |resize_filter| {
+        let config = PreprocessConfig {
+            input_size: InputSize {
+                width: 32,
+                height: 32,
+            },
+            resize_filter,
+            ..Default::default()
+        };
+        preprocess_with(&bytes, &config).unwrap()
+    };
+
+    let nearest = tensor(ResizeFilter::Nearest);
+    let lanczos = tensor(ResizeFilter::Lanczos3);
+    assert_eq!(nearest.shape, lanczos.shape);
+    assert_ne!(nearest.data, lanczos.data);
+    assert_eq!(
+        tensor(ResizeFilter::default()).data,
+        tensor(ResizeFilter::CatmullRom).data
+    );
+
+    assert_eq!(
+        "Lanczos3".parse::<ResizeFilter>(),
+        Ok(ResizeFilter::Lanczos3)
+    );
+    assert_eq!("nearest".parse::<ResizeFilter>(), Ok(ResizeFilter::Nearest));
+    assert!("box".parse::<ResizeFilter>().is_err());
+}
+
+#[test]
+fn rotation_accepts_only_right_angles() {
+    assert_eq!(Rotation::try_from(270), Ok(Rotation::Clockwise270));
+    assert!(Rotation::try_from(45).is_err());
+}
+
+#[test]
+fn downscale_only_rejects_images_smaller_than_the_input() {
+    let config = PreprocessConfig {
+        downscale_only: true,
+        ..Default::default()
+    };
+
+    let small = encode(solid(100, 80, [90, 90, 90]), ImageOutputFormat::Png);
+    let result = preprocess_with(&small, &config);
+    assert!(matches!(
+        result,
+        Err(ImageError::LowQuality(QualityRejection::WouldUpscale {
+            width: 100,
+            height: 80,
+            ..
+        }))
+    ));
+    assert!(preprocess_with(&small, &PreprocessConfig::default()).is_ok());
+
+    let large = encode(solid(300, 120, [90, 90, 90]), ImageOutputFormat::Png);
+    let tensor = preprocess_with(&large, &config).unwrap();
+    assert_eq!(tensor.shape, vec![1, 3, 224, 224]);
+}
+
+#[test]
+fn jpeg_prescale_decodes_close_to_the_target_size() {
+    let bytes = encode(solid(1792, 896, [120, 60, 30]), ImageOutputFormat::Jpeg(90));
+    let config = PreprocessConfig {
+        prescale_jpeg: true,
+        ..Default::default()
+    };
+
+    let image = decode(&bytes, &config).unwrap();
+    assert_eq!((image.width(), image.height()), (448, 224));
+
+    let full = decode(&bytes, &PreprocessConfig::default()).unwrap();
+    assert_eq!((full.width(), full.height()), (1792, 896));
+    assert_eq!(
+        preprocess_with(&bytes, &config).unwrap().shape,
+        vec![1, 3, 224, 224]
+    );
+}
+
+#[test]
+fn jpeg_prescale_respects_the_minimum_dimension_gate() {
+    let bytes = encode(
+        solid(1600, 1600, [120, 60, 30]),
+        ImageOutputFormat::Jpeg(90),
+    );
+    let config = PreprocessConfig {
+        prescale_jpeg: true,
+        quality: QualityGates {
+            min_dimension: Some(400),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let image = decode(&bytes, &config).unwrap();
+    assert_eq!((image.width(), image.height()), (400, 400));
+}
+
+#[test]
+fn configured_input_size_sets_resize_and_shape() {
+    let bytes = encode(solid(300, 200, [10, 20, 30]), ImageOutputFormat::Png);
+    let config = PreprocessConfig {
+        input_size: InputSize {
+            width: 112,
+            height: 112,
+        },
+        ..Default::default()
+    };
+
+    let tensor = preprocess_with(&bytes, &config).unwrap();
+    assert_eq!(tensor.shape, vec![1, 3, 112, 112]);
+    assert_eq!(tensor.data.len(), 3 * 112 * 112);
+}
+
+#[test]
+fn tensor_shape_matches_preprocessed_tensor() {
+    let bytes = encode(solid(40, 30, [10, 20, 30]), ImageOutputFormat::Png);
+    let config = PreprocessConfig {
+        input_size: InputSize {
+            width: 32,
+            height: 16,
+        },
+        channels: ChannelMode::Grayscale,
+        layout: TensorLayout::Nhwc,
+        ..Default::default()
+    };
+
+    assert_eq!(config.tensor_shape(), vec![1, 16, 32, 1]);
+    assert_eq!(
+        preprocess_with(&bytes, &config).unwrap().shape,
+        config.tensor_shape()
+    );
+}
+
+#[test]
+fn minus_one_to_one_maps_black_and_white_to_the_range_ends() {
+    let config = PreprocessConfig {
+        normalization: "minus_one_to_one".parse().unwrap(),
+        ..Default::default()
+    };
+    assert_eq!(config.normalization, Normalization::MINUS_ONE_TO_ONE);
+
+    for (pixel, expected) in [([0, 0, 0], -1.0), ([255, 255, 255], 1.0)] {
+        let bytes = encode(solid(4, 4, pixel), ImageOutputFormat::Png);
+        let tensor = preprocess_with(&bytes, &config).unwrap();
+        assert!(tensor.data.iter().all(|&value| value == expected));
+    }
+}
+
+#[test]
+fn per_image_standardization_centres_every_channel() {
+    let gradient = RgbImage::from_fn(32, 32, |x, y| {
+        Rgb([(x * 8) as u8, (y * 8) as u8, ((x + y) * 4) as u8])
+    });
+    let config = PreprocessConfig {
+        input_size: InputSize {
+            width: 32,
+            height: 32,
+        },
+        normalization: "per_image".parse().unwrap(),
+        ..Default::default()
+    };
+
+    let tensor = preprocess_with(&encode(gradient, ImageOutputFormat::Png), &config).unwrap();
+    for channel in tensor.data.chunks(32 * 32) {
+        let mean = channel.iter().sum::<f32>() / channel.len() as f32;
+        let variance = channel
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f32>()
+            / channel.len() as f32;
+        assert!(mean.abs() < 1e-4, "channel mean {mean}");
+        assert!((variance - 1.0).abs() < 1e-3, "channel variance {variance}");
+    }
+
+    let flat = encode(solid(32, 32, [40, 40, 40]), ImageOutputFormat::Png);
+    let tensor = preprocess_with(&flat, &config).unwrap();
+    assert!(tensor
+        .data
+        .iter()
+        .all(|value| value.is_finite() && value.abs() < 1e-3));
+}
+
+#[test]
+fn imagenet_normalization_applies_per_channel_mean_and_std() {
+    let bytes = encode(solid(16, 16, [255, 128, 0]), ImageOutputFormat::Png);
+    let config = PreprocessConfig {
+        normalization: Normalization::IMAGENET,
+        ..Default::default()
+    };
+
+    let tensor = preprocess_with(&bytes, &config).unwrap();
+    let plane = 224 * 224;
+    let expected = [
+        (1.0 - 0.485) / 0.229,
+        (128.0 / 255.0 - 0.456) / 0.224,
+        (0.0 - 0.406) / 0.225,
+    ];
+    for (channel, expected) in expected.into_iter().enumerate() {
+        let values = &tensor.data[channel * plane..(channel + 1) * plane];
+        assert!(values.iter().all(|value| (value - expected).abs() < 1e-5));
+    }
+}
+
+#[test]
+fn identity_normalization_keeps_unit_scaled_values() {
+    let bytes = encode(solid(16, 16, [255, 0, 51]), ImageOutputFormat::Png);
+
+    let tensor = preprocess_with(&bytes, &PreprocessConfig::default()).unwrap();
+    assert_eq!(tensor.data[0], 1.0);
+    assert_eq!(tensor.data[224 * 224], 0.0);
+    assert_eq!(tensor.data[2 * 224 * 224], 51.0 / 255.0);
+}
+
+#[test]
+fn sixteen_bit_grayscale_keeps_full_precision() {
+    // 1000 / 65535 would become 3 / 255 after truncation to 8 bits.
+    let gray = ImageBuffer::from_pixel(16, 16, Luma([1000u16]));
+    let mut bytes = Vec::new();
+    DynamicImage::ImageLuma16(gray)
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .unwrap();
+    let expected = 1000.0 / 65535.0;
+
+    let tensor = preprocess_with(&bytes, &PreprocessConfig::default()).unwrap();
+    assert_eq!(tensor.data.len(), 3 * 224 * 224);
+    assert!(tensor
+        .data
+        .iter()
+        .all(|value| (value - expected).abs() < 1e-6));
+
+    let config = PreprocessConfig {
+        normalization: Normalization::MINUS_ONE_TO_ONE,
+        ..Default::default()
+    };
+    let tensor = preprocess_with(&bytes, &config).unwrap();
+    assert!(tensor
+        .data
+        .iter()
+        .all(|value| (value - (expected * 2.0 - 1.0)).abs() < 1e-5));
+}
+
+#[test]
+fn letterbox_centres_landscape_images_between_fill_rows() {
+    let bytes = encode(solid(448, 224, [255, 255, 255]), ImageOutputFormat::Png);
+    let fill = [10, 20, 30];
+    let config = PreprocessConfig {
+        resize: ResizeMode::Letterbox { fill },
+        ..Default::default()
+    };
+
+    let tensor = preprocess_with(&bytes, &config).unwrap();
+    assert_eq!(tensor.shape, vec![1, 3, 224, 224]);
+
+    // 448x224 scales to 224x112, leaving 56 pad rows above and below.
+    let plane = 224 * 224;
+    for (channel, fill) in fill.into_iter().enumerate() {
+        let rows = |range: std::ops::Range<usize>| {
+            &tensor.data[channel * plane + range.start * 224..channel * plane + range.end * 224]
+        };
+        let fill = f32::from(fill) / 255.0;
+        assert!(rows(0..56).iter().all(|&value| value == fill));
+        assert!(rows(56..168).iter().all(|&value| value == 1.0));
+        assert!(rows(168..224).iter().all(|&value| value == fill));
+    }
+}
+
+#[test]
+fn resize_mode_parses_letterbox_fill() {
+    assert_eq!("stretch".parse(), Ok(ResizeMode::Stretch));
+    assert_eq!(
+        "letterbox".parse(),
+        Ok(ResizeMode::Letterbox { fill: [0, 0, 0] })
+    );
+    assert_eq!(
+        "letterbox:114,114,114".parse(),
+        Ok(ResizeMode::Letterbox {
+            fill: [114, 114, 114]
+        })
+    );
+    assert!("letterbox:1,2".parse::<ResizeMode>().is_err());
+}
+
+#[test]
+fn grayscale_mode_emits_one_rec601_luminance_plane() {
+    let bytes = encode(solid(64, 32, [255, 0, 0]), ImageOutputFormat::Png);
+    let config = PreprocessConfig {
+        channels: ChannelMode::Grayscale,
+        ..Default::default()
+    };
+
+    let tensor = preprocess_with(&bytes, &config).unwrap();
+    assert_eq!(tensor.shape, vec![1, 1, 224, 224]);
+    assert_eq!(tensor.data.len(), 224 * 224);
+    assert!(tensor
+        .data
+        .iter()
+        .all(|&value| (value - 0.299).abs() < 1e-6));
+}
+
+#[test]
+fn nhwc_layout_interleaves_channels_per_pixel() {
+    let pixels = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]];
+    let image = RgbImage::from_fn(2, 2, |x, y| Rgb(pixels[(y * 2 + x) as usize]));
+    let bytes = encode(image, ImageOutputFormat::Png);
+    let config = |layout| PreprocessConfig {
+        input_size: InputSize {
+            width: 2,
+            height: 2,
+        },
+        layout,
+        ..Default::default()
+    };
+
+    let nchw = preprocess_with(&bytes, &config(TensorLayout::Nchw)).unwrap();
+    assert_eq!(nchw.shape, vec![1, 3, 2, 2]);
+    assert_eq!(
+        nchw.data,
+        vec![1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0]
+    );
+
+    let nhwc = preprocess_with(&bytes, &config(TensorLayout::Nhwc)).unwrap();
+    assert_eq!(nhwc.shape, vec![1, 2, 2, 3]);
+    assert_eq!(
+        nhwc.data,
+        vec![1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0]
+    );
+}
+
+#[test]
+fn batch_preprocessing_stacks_images() {
+    let first = encode(solid(64, 48, [255, 0, 0]), ImageOutputFormat::Png);
+    let second = encode(solid(32, 32, [0, 0, 255]), ImageOutputFormat::Jpeg(90));
+
+    let tensor = preprocess_batch(&[&first, &second]).unwrap();
+    assert_eq!(tensor.shape, vec![2, 3, 224, 224]);
+    assert_eq!(tensor.data.len(), 2 * 3 * 224 * 224);
+    assert_eq!(tensor.data[0], 1.0);
+    assert_eq!(tensor.data[3 * 224 * 224], 0.0);
+}
+
+#[test]
+fn batch_preprocessing_reports_the_failing_index() {
+    let good = encode(solid(32, 32, [0, 0, 255]), ImageOutputFormat::Png);
+    let result = preprocess_batch(&[&good, b"not an image"]);
+
+    assert!(matches!(
+        result,
+        Err(ImageError::BatchItem { index: 1, .. })
+    ));
+    assert!(matches!(preprocess_batch(&[]), Err(ImageError::EmptyBatch)));
+}
+
+fn three_bands() -> RgbImage {
+    // 400x200 with a 100px red band, 200px green middle and 100px blue band.
+    RgbImage::from_fn(400, 200, |x, _| match x {
+        0..=99 => Rgb([255, 0, 0]),
+        100..=299 => Rgb([0, 255, 0]),
+        _ => Rgb([0, 0, 255]),
+    })
+}
+
+#[test]
+fn center_crop_keeps_a_centred_square_of_the_short_side() {
+    let cropped = center_crop(&DynamicImage::ImageRgb8(three_bands())).to_rgb8();
+
+    assert_eq!(cropped.dimensions(), (200, 200));
+    assert!(cropped.pixels().all(|pixel| pixel.0 == [0, 255, 0]));
+}
+
+#[test]
+fn center_crop_runs_before_resize_when_enabled() {
+    let bytes = encode(three_bands(), ImageOutputFormat::Png);
+    let config = PreprocessConfig {
+        center_crop: true,
+        ..Default::default()
+    };
+
+    let tensor = preprocess_with(&bytes, &config).unwrap();
+    let plane = 224 * 224;
+    assert!(tensor.data[..plane].iter().all(|&value| value == 0.0));
+    assert!(tensor.data[plane..2 * plane]
+        .iter()
+        .all(|&value| value == 1.0));
+}
+
+#[test]
+fn bgr_order_puts_the_blue_plane_first() {
+    let bytes = encode(solid(16, 16, [255, 128, 51]), ImageOutputFormat::Png);
+    let config = PreprocessConfig {
+        channel_order: ChannelOrder::Bgr,
+        ..Default::default()
+    };
+
+    let tensor = preprocess_with(&bytes, &config).unwrap();
+    let plane = 224 * 224;
+    assert_eq!(tensor.data[0], 51.0 / 255.0);
+    assert_eq!(tensor.data[plane], 128.0 / 255.0);
+    assert_eq!(tensor.data[2 * plane], 1.0);
+}
+
+#[test]
+fn bgr_order_keeps_imagenet_statistics_with_their_channels() {
+    let bytes = encode(solid(16, 16, [255, 128, 0]), ImageOutputFormat::Png);
+    let config = PreprocessConfig {
+        channel_order: ChannelOrder::Bgr,
+        normalization: Normalization::IMAGENET,
+        ..Default::default()
+    };
+
+    let tensor = preprocess_with(&bytes, &config).unwrap();
+    let plane = 224 * 224;
+    let expected = [
+        (0.0 - 0.406) / 0.225,
+        (128.0 / 255.0 - 0.456) / 0.224,
+        (1.0 - 0.485) / 0.229,
+    ];
+    for (channel, expected) in expected.into_iter().enumerate() {
+        let values = &tensor.data[channel * plane..(channel + 1) * plane];
+        assert!(values.iter().all(|value| (value - expected).abs() < 1e-5));
+    }
+}
+
+#[test]
+fn formats_outside_the_allowed_set_are_rejected() {
+    let config = PreprocessConfig::default();
+    for format in [ImageOutputFormat::Png, ImageOutputFormat::Jpeg(90)] {
+        let bytes = encode(solid(32, 32, [10, 200, 10]), format);
+        assert!(preprocess_with(&bytes, &config).is_ok());
+    }
+
+    // A BMP file header; detection only needs the signature.
+    let mut bmp = b"BM".to_vec();
+    bmp.resize(64, 0);
+    let result = preprocess_with(&bmp, &config);
+    assert!(
+        matches!(&result, Err(ImageError::FormatNotAllowed(format)) if format == "Bmp"),
+        "{result:?}"
+    );
+
+    let png_only = PreprocessConfig {
+        allowed_formats: "png".parse().unwrap(),
+        ..Default::default()
+    };
+    let jpeg = encode(solid(32, 32, [10, 200, 10]), ImageOutputFormat::Jpeg(90));
+    assert!(matches!(
+        preprocess_with(&jpeg, &png_only),
+        Err(ImageError::FormatNotAllowed(_))
+    ));
+}
+
+#[test]
+fn allowed_formats_parse_from_extensions() {
+    assert_eq!(
+        "jpg, png".parse::<AllowedFormats>(),
+        Ok(AllowedFormats::new(vec![
+            ImageFormat::Jpeg,
+            ImageFormat::Png
+        ]))
+    );
+    assert!("jpeg,tiffany".parse::<AllowedFormats>().is_err());
+}
+
+#[test]
+fn multi_frame_gif_uses_the_first_frame() {
+    let frame = |pixel: [u8; 4]| Frame::new(RgbaImage::from_pixel(4, 4, Rgba(pixel)));
+    let mut bytes = Vec::new();
+    GifEncoder::new(&mut bytes)
+        .encode_frames([frame([255, 0, 0, 255]), frame([0, 0, 255, 255])])
+        .unwrap();
+    let config = PreprocessConfig {
+        allowed_formats: AllowedFormats::new(vec![ImageFormat::Gif]),
+        ..Default::default()
+    };
+
+    let rgb = decode(&bytes, &config).unwrap().to_rgb8();
+    assert_eq!(rgb.dimensions(), (4, 4));
+    assert!(rgb.pixels().all(|pixel| pixel.0 == [255, 0, 0]));
+}
+
+#[test]
+fn transparency_is_flattened_over_the_background() {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 128])))
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .unwrap();
+
+    let rgb = decode(&bytes, &PreprocessConfig::default())
+        .unwrap()
+        .to_rgb8();
+    assert!(rgb.pixels().all(|pixel| pixel.0 == [255, 127, 127]));
+
+    let config = PreprocessConfig {
+        background: "0,0,0".parse().unwrap(),
+        ..Default::default()
+    };
+    let rgb = decode(&bytes, &config).unwrap().to_rgb8();
+    assert!(rgb.pixels().all(|pixel| pixel.0 == [128, 0, 0]));
+}
+
+#[test]
+fn blurry_images_are_rejected_before_inference() {
+    let config = PreprocessConfig {
+        input_size: InputSize {
+            width: 32,
+            height: 32,
+        },
+        quality: QualityGates {
+            min_blur_score: Some(100.0),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let sharp = RgbImage::from_fn(128, 128, |x, y| {
+        if (x / 4 + y / 4) % 2 == 0 {
+            Rgb([20, 20, 20])
+        } else {
+            Rgb([235, 235, 235])
+        }
+    });
+    preprocess_with(&encode(sharp, ImageOutputFormat::Png), &config).unwrap();
+
+    let uniform = encode(solid(128, 128, [120, 120, 120]), ImageOutputFormat::Png);
+    match preprocess_with(&uniform, &config) {
+        Err(ImageError::LowQuality(QualityRejection::Blurry { score, .. })) => {
+            assert_eq!(score, 0.0)
+        }
+        other => panic!("expected a blur rejection, got {other:?}"),
+    }
+}
+
+#[test]
+fn images_below_the_minimum_dimension_are_rejected() {
+    let config = PreprocessConfig {
+        quality: QualityGates {
+            min_dimension: Some(64),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let small = encode(solid(16, 16, [90, 90, 90]), ImageOutputFormat::Png);
+    match preprocess_with(&small, &config) {
+        Err(ImageError::LowQuality(rejection)) => assert_eq!(
+            rejection.to_string(),
+            "image is 16x16, below the minimum dimension of 64"
+        ),
+        other => panic!("expected a minimum dimension rejection, got {other:?}"),
+    }
+
+    let large = encode(solid(128, 128, [90, 90, 90]), ImageOutputFormat::Png);
+    assert!(preprocess_with(&large, &config).is_ok());
+}
+
+#[test]
+fn preprocess_meta_records_original_dimensions() {
+    let bytes = encode(solid(300, 120, [10, 20, 30]), ImageOutputFormat::Png);
+    let config = PreprocessConfig {
+        rotation: Rotation::Clockwise90,
+        resize: ResizeMode::Letterbox { fill: [0; 3] },
+        ..Default::default()
+    };
+
+    let (tensor, meta) = preprocess_with_meta(&bytes, &config).unwrap();
+    assert_eq!((meta.original_width, meta.original_height), (300, 120));
+    assert_eq!(meta.rotation, Rotation::Clockwise90);
+    assert_eq!(meta.resize, config.resize);
+    assert!(!meta.custom_pipeline);
+    assert_eq!(meta.shape, tensor.shape);
+    assert_eq!(meta.shape, vec![1, 3, 224, 224]);
+}
@@ -0,0 +1,72 @@
+use image::{DynamicImage, Luma, Rgb, RgbImage};
+use rust_service::quality::{assess, blur_score, QualityGates, QualityRejection};
+
+fn checkerboard() -> DynamicImage {
+    DynamicImage::ImageLuma8(image::GrayImage::from_fn(32, 32, |x, y| {
+        if (x + y) % 2 == 0 {
+            Luma([40])
+        } else {
+            Luma([215])
+        }
+    }))
+}
+
+fn gradient() -> DynamicImage {
+    DynamicImage::ImageLuma8(image::GrayImage::from_fn(32, 32, |x, _| {
+        Luma([(x * 8) as u8])
+    }))
+}
+
+#[test]
+fn sharp_edges_score_higher_than_smooth_gradients() {
+    let sharp = assess(&checkerboard());
+    let smooth = assess(&gradient());
+
+    assert!(sharp.sharpness > 0.9, "{sharp:?}");
+    assert!(smooth.sharpness < 0.1, "{smooth:?}");
+}
+
+#[test]
+fn exposure_extremes_score_zero_brightness() {
+    let black = assess(&DynamicImage::ImageRgb8(RgbImage::from_pixel(
+        16,
+        16,
+        Rgb([0, 0, 0]),
+    )));
+
+    assert_eq!(black.brightness, 0.0);
+    assert_eq!(black.contrast, 0.0);
+    assert_eq!(black.overall, 0.0);
+}
+
+#[test]
+fn overall_is_the_weakest_component() {
+    let quality = assess(&checkerboard());
+
+    let weakest = quality
+        .sharpness
+        .min(quality.brightness)
+        .min(quality.contrast);
+    assert_eq!(quality.overall, weakest);
+    assert!(quality.brightness > 0.95);
+}
+
+#[test]
+fn blur_gate_rejects_a_uniform_image_and_keeps_a_sharp_one() {
+    let gates = QualityGates {
+        min_blur_score: Some(100.0),
+        ..Default::default()
+    };
+    let uniform = DynamicImage::ImageLuma8(image::GrayImage::from_pixel(32, 32, Luma([128])));
+
+    assert!(blur_score(&checkerboard(), 32, 32) > 100.0);
+    gates.check_blur(&checkerboard(), 32, 32).unwrap();
+    assert!(matches!(
+        gates.check_blur(&uniform, 32, 32),
+        Err(QualityRejection::Blurry { score, min }) if score == 0.0 && min == 100.0
+    ));
+    // Without a threshold the gate is off.
+    QualityGates::default()
+        .check_blur(&uniform, 32, 32)
+        .unwrap();
+}
@@ -0,0 +1,42 @@
+use rust_service::labels::{Labels, LabelsError};
+
+const LABELS: &str = "genuine\nprinted_photo\n\nscreen_replay\n";
+
+#[test]
+fn argmax_maps_to_its_label() {
+    let labels = Labels::parse(LABELS).unwrap();
+    assert_eq!(labels.len(), 4);
+    assert_eq!(
+        labels.predicted(&[0.1, 0.7, 0.05, 0.15]),
+        Some("printed_photo")
+    );
+    assert_eq!(
+        labels.predicted(&[2.0, -1.0, 0.5, 3.5]),
+        Some("screen_replay")
+    );
+}
+
+#[test]
+fn unlabelled_and_out_of_range_classes_have_no_label() {
+    let labels = Labels::parse(LABELS).unwrap();
+    assert_eq!(labels.predicted(&[0.1, 0.1, 0.9, 0.1]), None);
+    assert_eq!(labels.predicted(&[0.1, 0.1, 0.1, 0.1, 0.9]), None);
+    assert_eq!(labels.predicted(&[]), None);
+    assert_eq!(labels.get(10), None);
+}
+
+#[test]
+fn labels_load_from_a_file() {
+    let path = std::env::temp_dir().join(format!("labels-{}.txt", std::process::id()));
+    std::fs::write(&path, LABELS).unwrap();
+    let labels = Labels::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(labels.get(0), Some("genuine"));
+    assert_eq!(labels.get(3), Some("screen_replay"));
+}
+
+#[test]
+fn empty_labels_file_is_rejected() {
+    assert!(matches!(Labels::parse("\n \n"), Err(LabelsError::Empty)));
+}
@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+use rust_service::limiter::{
+    resource_exhausted, ConcurrencyLimiter, UserRateLimiter, RETRY_AFTER_HEADER,
+};
+
+#[test]
+fn rejects_when_saturated_with_retry_hint() {
+    let limiter = ConcurrencyLimiter::new(1);
+    let permit = limiter.try_acquire().unwrap();
+
+    let retry_after = limiter.try_acquire().err().unwrap();
+    assert!(retry_after >= Duration::from_millis(50));
+    assert!(retry_after <= Duration::from_secs(30));
+
+    drop(permit);
+    assert!(limiter.try_acquire().is_ok());
+}
+
+#[test]
+fn status_carries_retry_after_metadata() {
+    let status = resource_exhausted("busy", Duration::from_millis(250));
+
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+    assert_eq!(status.metadata().get(RETRY_AFTER_HEADER).unwrap(), "250");
+}
+
+#[test]
+fn rate_limit_is_tracked_per_user() {
+    let limiter = UserRateLimiter::new(1.0, 3).unwrap();
+    let now = Instant::now();
+
+    for _ in 0..3 {
+        limiter.check_at("alice", now).unwrap();
+    }
+    let retry_after = limiter.check_at("alice", now).unwrap_err();
+    let status = resource_exhausted("verification rate limit exceeded", retry_after);
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+    assert_eq!(retry_after, Duration::from_secs(1));
+
+    assert!(limiter.check_at("bob", now).is_ok());
+    assert!(limiter
+        .check_at("alice", now + Duration::from_secs(1))
+        .is_ok());
+}
+
+#[test]
+fn idle_rate_buckets_are_evicted() {
+    let limiter = UserRateLimiter::new(2.0, 4).unwrap();
+    let now = Instant::now();
+    limiter.check_at("alice", now).unwrap();
+    limiter.check_at("bob", now).unwrap();
+    assert_eq!(limiter.tracked_users(), 2);
+
+    limiter
+        .check_at("carol", now + Duration::from_secs(2))
+        .unwrap();
+    assert_eq!(limiter.tracked_users(), 1);
+}
+
+#[test]
+fn rate_limiter_rejects_invalid_settings() {
+    assert!(UserRateLimiter::new(0.0, 1).is_err());
+    assert!(UserRateLimiter::new(1.0, 0).is_err());
+}
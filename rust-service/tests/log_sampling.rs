@@ -0,0 +1,30 @@
+use rust_service::sampling::LogSampler;
+use uuid::Uuid;
+
+#[test]
+fn extreme_rates_are_exact() {
+    let never = LogSampler::new(0.0).unwrap();
+    let always = LogSampler::new(1.0).unwrap();
+
+    for _ in 0..1000 {
+        let id = Uuid::new_v4();
+        assert!(!never.should_sample(&id));
+        assert!(always.should_sample(&id));
+    }
+}
+
+#[test]
+fn samples_roughly_the_configured_fraction() {
+    let sampler = LogSampler::new(0.25).unwrap();
+    let sampled = (0..10_000)
+        .filter(|_| sampler.should_sample(&Uuid::new_v4()))
+        .count();
+
+    assert!((2_000..3_000).contains(&sampled), "sampled {sampled}");
+}
+
+#[test]
+fn rejects_rates_outside_unit_interval() {
+    assert!(LogSampler::new(1.5).is_err());
+    assert!(LogSampler::new(-0.1).is_err());
+}
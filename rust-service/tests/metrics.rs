@@ -0,0 +1,69 @@
+use std::{collections::BTreeMap, net::SocketAddr, sync::Arc, time::Duration};
+
+use rust_service::{
+    metrics::{self, Metrics},
+    triton_client::BatchUtilization,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time,
+};
+
+#[test]
+fn verification_counters_increment() {
+    let metrics = Metrics::new();
+    metrics.record_verification(true);
+    metrics.record_verification(false);
+    metrics.observe_stage("inference", Duration::from_millis(20));
+
+    assert_eq!(metrics.verifications_total(), 2);
+    let text = metrics.encode();
+    assert!(text.contains("verifications_total 2"), "{text}");
+    assert!(text.contains("verifications_succeeded_total 1"), "{text}");
+    assert!(text.contains("verifications_failed_total 1"), "{text}");
+    assert!(
+        text.contains(r#"verification_stage_seconds_count{stage="inference"} 1"#),
+        "{text}"
+    );
+}
+
+#[tokio::test]
+async fn metrics_are_served_over_http() {
+    let addr: SocketAddr = "127.0.0.1:50082".parse().unwrap();
+    let metrics = Arc::new(Metrics::new());
+    metrics.record_verification(true);
+    tokio::spawn(metrics::serve(Arc::clone(&metrics), addr));
+    time::sleep(Duration::from_millis(50)).await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.contains("verifications_total 1"), "{response}");
+}
+
+#[test]
+fn batch_utilization_observes_only_new_executions() {
+    let metrics = Metrics::new();
+    let first = BatchUtilization {
+        executions_by_batch_size: BTreeMap::from([(1, 2), (4, 1)]),
+        average_queue_time: Some(Duration::from_millis(5)),
+    };
+    metrics.record_batch_utilization(&first, &BatchUtilization::default());
+    let second = BatchUtilization {
+        executions_by_batch_size: BTreeMap::from([(1, 2), (4, 3)]),
+        average_queue_time: Some(Duration::from_millis(8)),
+    };
+    metrics.record_batch_utilization(&second, &first);
+
+    let text = metrics.encode();
+    assert!(text.contains("triton_batch_size_count 5"), "{text}");
+    assert!(text.contains("triton_batch_size_sum 14"), "{text}");
+    assert!(text.contains("triton_batch_queue_seconds 0.008"), "{text}");
+}
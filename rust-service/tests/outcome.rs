@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use rust_service::{decision::Decision, outcome::VerificationOutcome, timing::ServerTiming};
+
+#[test]
+fn outcome_serializes_to_json() {
+    let mut timing = ServerTiming::default();
+    timing.record("inference", Duration::from_millis(12));
+
+    let outcome = VerificationOutcome {
+        request_id: "req-1".to_string(),
+        score: 0.5,
+        decision: Decision::Review,
+        embedding: vec![0.25, -0.5],
+        timings: VerificationOutcome::stage_timings(&timing),
+        model_fingerprint: "0123456789abcdef".to_string(),
+    };
+
+    let json = serde_json::to_value(&outcome).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "request_id": "req-1",
+            "score": 0.5,
+            "decision": "review",
+            "embedding": [0.25, -0.5],
+            "timings": [{"stage": "inference", "millis": 12.0}],
+            "model_fingerprint": "0123456789abcdef",
+        })
+    );
+}
@@ -0,0 +1,100 @@
+use image::{DynamicImage, Rgb, RgbImage};
+use rust_service::{
+    image::{tensor_from_image, PreprocessConfig},
+    pipeline::{PreprocessPipeline, PreprocessStep},
+};
+
+fn split_image() -> DynamicImage {
+    // Left half black, right half white.
+    DynamicImage::ImageRgb8(RgbImage::from_fn(8, 4, |x, _| {
+        if x < 4 {
+            Rgb([0, 0, 0])
+        } else {
+            Rgb([255, 255, 255])
+        }
+    }))
+}
+
+#[test]
+fn default_pipeline_matches_the_built_in_preprocessing() {
+    let image = split_image();
+    let tensor = PreprocessPipeline::default().run(&image).unwrap();
+
+    assert_eq!(tensor.shape, vec![1, 3, 224, 224]);
+    assert_eq!(
+        tensor.data,
+        tensor_from_image(&image, &PreprocessConfig::default()).data
+    );
+}
+
+#[test]
+fn steps_run_in_the_configured_order() {
+    let pipeline = PreprocessPipeline::from_json(
+        r#"[
+            {"step": "crop", "x": 0.5, "y": 0.0, "width": 0.5, "height": 1.0},
+            {"step": "resize", "width": 2, "height": 2},
+            {"step": "to_tensor"},
+            {"step": "normalize", "mean": [0.5, 0.5, 0.5], "std": [0.5, 0.5, 0.5]}
+        ]"#,
+    )
+    .unwrap();
+
+    let tensor = pipeline.run(&split_image()).unwrap();
+    assert_eq!(tensor.shape, vec![1, 3, 2, 2]);
+    assert!(tensor.data.iter().all(|&value| value == 1.0));
+}
+
+#[test]
+fn invalid_orderings_are_rejected() {
+    let resize_after_tensor = vec![
+        PreprocessStep::ToTensor,
+        PreprocessStep::Resize {
+            width: 2,
+            height: 2,
+        },
+    ];
+    let normalize_before_tensor = vec![
+        PreprocessStep::Normalize {
+            mean: [0.0; 3],
+            std: [1.0; 3],
+        },
+        PreprocessStep::ToTensor,
+    ];
+    let missing_tensor = vec![PreprocessStep::Rotate { degrees: 90 }];
+
+    for steps in [resize_after_tensor, normalize_before_tensor, missing_tensor] {
+        assert!(PreprocessPipeline::new(steps).is_err());
+    }
+    assert!(PreprocessPipeline::new(vec![
+        PreprocessStep::Rotate { degrees: 45 },
+        PreprocessStep::ToTensor
+    ])
+    .is_err());
+}
+
+#[test]
+fn expected_shape_follows_the_pipeline() {
+    let pipeline = PreprocessPipeline::from_json(
+        r#"[
+            {"step": "resize", "width": 64, "height": 32},
+            {"step": "rotate", "degrees": 90},
+            {"step": "crop", "x": 0.0, "y": 0.0, "width": 1.0, "height": 0.5},
+            {"step": "to_tensor"}
+        ]"#,
+    )
+    .unwrap();
+    let config = PreprocessConfig {
+        pipeline: Some(pipeline.clone()),
+        ..Default::default()
+    };
+
+    let image = DynamicImage::ImageRgb8(RgbImage::new(100, 80));
+    assert_eq!(config.tensor_shape(), vec![1, 3, 32, 32]);
+    assert_eq!(pipeline.run(&image).unwrap().shape, config.tensor_shape());
+
+    let variable = PreprocessConfig {
+        pipeline: Some(PreprocessPipeline::new(vec![PreprocessStep::ToTensor]).unwrap()),
+        ..Default::default()
+    };
+    assert_eq!(variable.tensor_shape(), vec![1, 3, -1, -1]);
+}
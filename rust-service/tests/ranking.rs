@@ -0,0 +1,81 @@
+use rust_service::{
+    decision::Decision,
+    ranking::{apply_min_margin, rank, top_k, Candidate},
+};
+
+fn candidate(id: &str, score: f32) -> Candidate {
+    Candidate {
+        id: id.to_string(),
+        score,
+    }
+}
+
+#[test]
+fn ranks_by_score_and_reports_margin() {
+    let ranking = rank(
+        vec![
+            candidate("a", 0.5),
+            candidate("b", 0.75),
+            candidate("c", 0.25),
+        ],
+        2,
+    );
+
+    let ids: Vec<_> = ranking.matches.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, vec!["b", "a"]);
+    assert_eq!(ranking.top_score, Some(0.75));
+    assert_eq!(ranking.second_score, Some(0.5));
+    assert_eq!(ranking.margin, Some(0.25));
+}
+
+#[test]
+fn margin_is_computed_before_truncating_to_k() {
+    let ranking = rank(vec![candidate("a", 0.5), candidate("b", 0.75)], 1);
+
+    assert_eq!(ranking.matches.len(), 1);
+    assert_eq!(ranking.margin, Some(0.25));
+}
+
+#[test]
+fn small_margin_downgrades_accept_to_review() {
+    let close = rank(vec![candidate("a", 0.9), candidate("b", 0.875)], 1);
+    let clear = rank(vec![candidate("a", 0.9), candidate("b", 0.5)], 1);
+
+    assert_eq!(
+        apply_min_margin(Decision::Accept, &close, Some(0.1)),
+        Decision::Review
+    );
+    assert_eq!(
+        apply_min_margin(Decision::Accept, &clear, Some(0.1)),
+        Decision::Accept
+    );
+    assert_eq!(
+        apply_min_margin(Decision::Reject, &close, Some(0.1)),
+        Decision::Reject
+    );
+    assert_eq!(
+        apply_min_margin(Decision::Accept, &close, None),
+        Decision::Accept
+    );
+}
+
+#[test]
+fn single_candidate_has_no_margin() {
+    let ranking = rank(vec![candidate("a", 0.9)], 5);
+
+    assert_eq!(ranking.second_score, None);
+    assert_eq!(ranking.margin, None);
+    assert_eq!(
+        apply_min_margin(Decision::Accept, &ranking, Some(0.1)),
+        Decision::Accept
+    );
+}
+
+#[test]
+fn top_k_returns_the_highest_scores_with_their_indices() {
+    let scores = [0.1, 0.7, 0.05, 0.9, 0.7, 0.3];
+
+    assert_eq!(top_k(&scores, 3), vec![(3, 0.9), (1, 0.7), (4, 0.7)]);
+    assert_eq!(top_k(&scores, 0), Vec::new());
+    assert_eq!(top_k(&scores[..2], 3), vec![(1, 0.7), (0, 0.1)]);
+}
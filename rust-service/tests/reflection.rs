@@ -0,0 +1,21 @@
+use prost::Message;
+use prost_types::FileDescriptorSet;
+use rust_service::verify::FILE_DESCRIPTOR_SET;
+
+#[test]
+fn descriptor_set_describes_the_verify_services() {
+    let set = FileDescriptorSet::decode(FILE_DESCRIPTOR_SET).unwrap();
+    let services: Vec<String> = set
+        .file
+        .iter()
+        .flat_map(|file| {
+            file.service
+                .iter()
+                .map(move |service| format!("{}.{}", file.package(), service.name()))
+        })
+        .collect();
+
+    assert!(services.contains(&"verify.ImageProcessor".to_string()));
+    assert!(services.contains(&"verify.ModelAdmin".to_string()));
+    assert!(!services.iter().any(|name| name.starts_with("inference.")));
+}
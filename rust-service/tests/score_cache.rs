@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use rust_service::score_cache::ScoreCache;
+
+#[test]
+fn least_recently_used_entry_is_evicted() {
+    let cache = ScoreCache::new(2, Duration::from_secs(60));
+    let (a, b, c) = (
+        ScoreCache::key(b"a", ""),
+        ScoreCache::key(b"b", ""),
+        ScoreCache::key(b"c", ""),
+    );
+    cache.insert(a, vec![1.0]);
+    cache.insert(b, vec![2.0]);
+    assert!(cache.get(&a).is_some());
+
+    cache.insert(c, vec![3.0]);
+    assert!(cache.get(&b).is_none());
+    assert_eq!(*cache.get(&a).unwrap(), vec![1.0]);
+    assert_eq!(*cache.get(&c).unwrap(), vec![3.0]);
+}
+
+#[test]
+fn expired_entries_are_not_served() {
+    let cache = ScoreCache::new(4, Duration::ZERO);
+    let key = ScoreCache::key(b"image", "");
+    cache.insert(key, vec![1.0]);
+    assert!(cache.get(&key).is_none());
+}
+
+#[test]
+fn variant_is_part_of_the_key() {
+    assert_ne!(
+        ScoreCache::key(b"image", "model-a"),
+        ScoreCache::key(b"image", "model-b")
+    );
+}
+
+#[test]
+fn repeated_hits_keep_recency_order() {
+    let cache = ScoreCache::new(2, Duration::from_secs(60));
+    let (a, b, c) = (
+        ScoreCache::key(b"a", ""),
+        ScoreCache::key(b"b", ""),
+        ScoreCache::key(b"c", ""),
+    );
+    cache.insert(a, vec![1.0]);
+    cache.insert(b, vec![2.0]);
+    for _ in 0..10 {
+        assert!(cache.get(&a).is_some());
+    }
+    cache.insert(b, vec![2.5]);
+    assert!(cache.get(&a).is_some());
+
+    cache.insert(c, vec![3.0]);
+    assert!(cache.get(&b).is_none());
+    assert!(cache.get(&a).is_some());
+    assert!(cache.get(&c).is_some());
+}
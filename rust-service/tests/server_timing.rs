@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use rust_service::timing::{ServerTiming, SERVER_TIMING_HEADER};
+use tonic::Response;
+
+#[test]
+fn formats_entries_in_recording_order() {
+    let mut timing = ServerTiming::default();
+    timing.record("decode", Duration::from_micros(1250));
+    timing.record("inference", Duration::from_millis(12));
+
+    assert_eq!(
+        timing.header_value(),
+        "decode;dur=1.250, inference;dur=12.000"
+    );
+}
+
+#[test]
+fn attaches_header_to_response_metadata() {
+    let mut timing = ServerTiming::default();
+    timing.record("preprocess", Duration::from_millis(3));
+
+    let mut response = Response::new(());
+    timing.attach(&mut response);
+
+    assert_eq!(
+        response.metadata().get(SERVER_TIMING_HEADER).unwrap(),
+        "preprocess;dur=3.000"
+    );
+}
@@ -0,0 +1,47 @@
+use std::{net::SocketAddr, time::Duration};
+
+use rust_service::shutdown::{self, ShutdownReason};
+use tokio::{sync::oneshot, time};
+use tonic::transport::{Endpoint, Server};
+
+#[tokio::test]
+async fn trigger_resolves_as_a_requested_shutdown() {
+    let (trigger, triggered) = oneshot::channel::<()>();
+    trigger.send(()).unwrap();
+
+    let reason = shutdown::wait_for(async {
+        let _ = triggered.await;
+    })
+    .await;
+    assert_eq!(reason, ShutdownReason::Requested);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn server_stops_accepting_connections_after_shutdown() {
+    let addr: SocketAddr = "127.0.0.1:50081".parse().unwrap();
+    let (_reporter, health) = tonic_health::server::health_reporter();
+    let (trigger, triggered) = oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        Server::builder()
+            .add_service(health)
+            .serve_with_shutdown(
+                addr,
+                shutdown::signal(async {
+                    let _ = triggered.await;
+                }),
+            )
+            .await
+            .unwrap();
+    });
+    time::sleep(Duration::from_millis(50)).await;
+
+    let endpoint = Endpoint::from_shared(format!("http://{addr}")).unwrap();
+    endpoint.connect().await.unwrap();
+
+    trigger.send(()).unwrap();
+    time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server did not stop")
+        .unwrap();
+    assert!(endpoint.connect().await.is_err());
+}
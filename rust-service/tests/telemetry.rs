@@ -0,0 +1,40 @@
+#![cfg(feature = "otel")]
+
+use opentelemetry::trace::TraceContextExt;
+use rust_service::telemetry::parent_context;
+use tonic::metadata::MetadataMap;
+
+#[test]
+fn trace_context_is_extracted_from_request_metadata() {
+    let mut metadata = MetadataMap::new();
+    metadata.insert(
+        "traceparent",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+            .parse()
+            .unwrap(),
+    );
+    metadata.insert("tracestate", "vendor=opaque".parse().unwrap());
+
+    let context = parent_context(&metadata);
+    let span = context.span();
+    let parent = span.span_context();
+    assert!(parent.is_valid());
+    assert!(parent.is_remote());
+    assert!(parent.is_sampled());
+    assert_eq!(
+        parent.trace_id().to_string(),
+        "4bf92f3577b34da6a3ce929d0e0e4736"
+    );
+    assert_eq!(parent.span_id().to_string(), "00f067aa0ba902b7");
+    assert_eq!(parent.trace_state().get("vendor"), Some("opaque"));
+}
+
+#[test]
+fn requests_without_trace_context_start_a_new_trace() {
+    let context = parent_context(&MetadataMap::new());
+    assert!(!context.span().span_context().is_valid());
+
+    let mut malformed = MetadataMap::new();
+    malformed.insert("traceparent", "not-a-traceparent".parse().unwrap());
+    assert!(!parent_context(&malformed).span().span_context().is_valid());
+}
@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use rust_service::triton_client::{Keepalive, TritonClient};
+
+fn client() -> TritonClient {
+    TritonClient::new(
+        "http://triton:8001",
+        "model",
+        "input",
+        "output",
+        false,
+        None,
+    )
+}
+
+#[test]
+fn keepalive_is_enabled_by_default() {
+    assert_eq!(
+        client().keepalive(),
+        Some(Keepalive {
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(20),
+        })
+    );
+}
+
+#[test]
+fn keepalive_follows_configuration() {
+    let keepalive = Keepalive {
+        interval: Duration::from_secs(10),
+        timeout: Duration::from_secs(5),
+    };
+    assert_eq!(
+        client().with_keepalive(Some(keepalive)).keepalive(),
+        Some(keepalive)
+    );
+    assert_eq!(client().with_keepalive(None).keepalive(), None);
+}
@@ -1,76 +1,1539 @@
-use std::{collections::HashMap, net::SocketAddr, pin::Pin, time::Duration};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use image::{DynamicImage, ImageOutputFormat, Rgb, RgbImage};
+use rust_service::{
+    deadline,
+    decision::VerifyThreshold,
+    errors::FieldViolations,
+    face::{FaceBox, FaceDetector, FaceError},
+    image::{preprocess_with, InputSize, PreprocessConfig, TensorLayout},
+    liveness::{Liveness, LivenessCheck},
+    model_spec::{ModelSpecError, ModelSpecs},
+    score_cache::{CacheKey, ScoreCache},
+    triton_client::{
+        encode_raw_fp16, encode_raw_fp32,
+        inference::{
+            self,
+            grpc_inference_service_server::{GrpcInferenceService, GrpcInferenceServiceServer},
+            infer_parameter::ParameterChoice,
+            model_infer_response, InferParameter, InferTensorContents, ModelInferRequest,
+            ModelInferResponse,
+        },
+        InputDtype, ModelAllowlist, TritonClient, TritonError,
+    },
+    verify::{image_processor_client::ImageProcessorClient, VerifyRequest},
+    ImageTensor,
+};
+use tokio::{sync::oneshot, task::JoinHandle, time};
+use tokio_stream::{Stream, StreamExt};
+#[cfg(feature = "gzip")]
+use tonic::codec::CompressionEncoding;
+use tonic::{async_trait, transport::Server, Code, Request, Response, Status};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn infer_request_serializes_expected_tensor() {
+    let addr: SocketAddr = "127.0.0.1:50070".parse().unwrap();
+    let model_name = "test-model".to_string();
+    let input_name = "input".to_string();
+    let output_name = "embedding".to_string();
+    let expected_shape = vec![1, 3, 2, 1];
+
+    let mock_service = MockTriton::new(
+        model_name.clone(),
+        input_name.clone(),
+        output_name.clone(),
+        expected_shape.clone(),
+    );
+
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        model_name,
+        input_name,
+        output_name,
+        false,
+        None,
+    );
+
+    let tensor = ImageTensor {
+        shape: expected_shape,
+        data: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+    };
+
+    let scores = client.infer(&tensor).await.unwrap();
+    assert_eq!(scores, vec![0.25, 0.75]);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn infer_pads_batch_and_discards_padded_outputs() {
+    let addr: SocketAddr = "127.0.0.1:50071".parse().unwrap();
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        vec![2, 3, 2, 1],
+    );
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .with_batch_padding(2);
+
+    let tensor = ImageTensor {
+        shape: vec![1, 3, 2, 1],
+        data: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+    };
+
+    let scores = client.infer(&tensor).await.unwrap();
+    assert_eq!(scores, vec![0.25]);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn eager_client_infers_without_lazy_connect() {
+    let addr: SocketAddr = "127.0.0.1:50072".parse().unwrap();
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        vec![1, 3, 2, 1],
+    );
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .connect_eager()
+    .await
+    .unwrap();
+
+    let tensor = ImageTensor {
+        shape: vec![1, 3, 2, 1],
+        data: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+    };
+
+    let (first, second) = tokio::join!(client.infer(&tensor), client.infer(&tensor));
+    assert_eq!(first.unwrap(), vec![0.25, 0.75]);
+    assert_eq!(second.unwrap(), vec![0.25, 0.75]);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn infer_with_model_discovers_and_caches_tensor_names() {
+    let addr: SocketAddr = "127.0.0.1:50073".parse().unwrap();
+    let mock_service = MockTriton::new(
+        "other-model".to_string(),
+        "pixels".to_string(),
+        "scores".to_string(),
+        vec![1, 3, 2, 1],
+    );
+    let metadata_calls = Arc::clone(&mock_service.metadata_calls);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    );
+    let tensor = ImageTensor {
+        shape: vec![1, 3, 2, 1],
+        data: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+    };
+
+    for _ in 0..2 {
+        let scores = client
+            .infer_with_model("other-model", &tensor, "req-1")
+            .await
+            .unwrap();
+        assert_eq!(scores, vec![0.25, 0.75]);
+    }
+    assert_eq!(metadata_calls.load(Ordering::SeqCst), 1);
+
+    assert!(client
+        .infer_with_model("missing-model", &tensor, "req-2")
+        .await
+        .is_err());
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn batched_output_is_split_per_element() {
+    let addr: SocketAddr = "127.0.0.1:50074".parse().unwrap();
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        vec![2, 3, 1, 1],
+    )
+    .with_output_shape(vec![2, 1]);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    );
+    let batch = ImageTensor {
+        shape: vec![2, 3, 1, 1],
+        data: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+    };
+    let rows = client.infer_batched(&batch, "").await.unwrap();
+    assert_eq!(rows, vec![vec![0.25], vec![0.75]]);
+
+    let padded = client.clone().with_batch_padding(2);
+    let single = ImageTensor {
+        shape: vec![1, 3, 1, 1],
+        data: vec![0.1, 0.2, 0.3],
+    };
+    let rows = padded.infer_batched(&single, "").await.unwrap();
+    assert_eq!(rows, vec![vec![0.25]]);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn negative_or_overflowing_output_shape_is_rejected() {
+    let batch = ImageTensor {
+        shape: vec![2, 3, 1, 1],
+        data: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+    };
+    for (port, shape) in [(50103, vec![2, -1, -1]), (50104, vec![2, i64::MAX, 2])] {
+        let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+        let mock_service = MockTriton::new(
+            "test-model".to_string(),
+            "input".to_string(),
+            "embedding".to_string(),
+            vec![2, 3, 1, 1],
+        )
+        .with_output_shape(shape.clone());
+        let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+        let client = TritonClient::new(
+            format!("http://{}", addr),
+            "test-model",
+            "input",
+            "embedding",
+            false,
+            None,
+        );
+        let result = client.infer_batched(&batch, "").await;
+        assert!(
+            matches!(result, Err(TritonError::InvalidResponse(_))),
+            "{shape:?}: {result:?}"
+        );
+
+        shutdown_tx.send(()).unwrap();
+        server.await.unwrap();
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn load_and_unload_reach_the_model_repository() {
+    let addr: SocketAddr = "127.0.0.1:50075".parse().unwrap();
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        vec![1, 3, 2, 1],
+    );
+    let repository_calls = Arc::clone(&mock_service.repository_calls);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    );
+    client.load_model("liveness").await.unwrap();
+    client.unload_model("legacy").await.unwrap();
+
+    assert_eq!(
+        *repository_calls.lock().unwrap(),
+        vec!["load:liveness".to_string(), "unload:legacy".to_string()]
+    );
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[test]
+fn raw_input_encoding_is_little_endian() {
+    let bytes = encode_raw_fp32(&[1.0, -2.5, 0.0]);
+    assert_eq!(
+        bytes,
+        vec![0x00, 0x00, 0x80, 0x3F, 0x00, 0x00, 0x20, 0xC0, 0x00, 0x00, 0x00, 0x00]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn fp16_input_is_sent_as_raw_half_precision() {
+    let addr: SocketAddr = "127.0.0.1:50076".parse().unwrap();
+    let shape = vec![1, 3, 2, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    );
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .with_input_dtype(InputDtype::Fp16);
+    let tensor = ImageTensor {
+        shape,
+        data: vec![1.0, -2.0, 0.5, 0.0, 65504.0, -0.25],
+    };
+    assert_eq!(client.infer(&tensor).await.unwrap(), vec![0.25, 0.75]);
+
+    let requests = requests.lock().unwrap();
+    let request = &requests[0];
+    assert_eq!(request.inputs[0].datatype, "FP16");
+    assert!(request.inputs[0].contents.is_none());
+    assert_eq!(
+        request.raw_input_contents,
+        vec![vec![
+            0x00, 0x3C, 0x00, 0xC0, 0x00, 0x38, 0x00, 0x00, 0xFF, 0x7B, 0x00, 0xB4
+        ]]
+    );
+    drop(requests);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn infer_retries_after_an_unavailable_server() {
+    let addr: SocketAddr = "127.0.0.1:50077".parse().unwrap();
+    let shape = vec![1, 3, 2, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    )
+    .with_failures(1);
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = |attempts| {
+        TritonClient::new(
+            format!("http://{}", addr),
+            "test-model",
+            "input",
+            "embedding",
+            false,
+            None,
+        )
+        .with_retry(attempts, Duration::from_millis(10))
+    };
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+    };
+
+    let scores = client(3).infer(&tensor).await.unwrap();
+    assert_eq!(scores, vec![0.25, 0.75]);
+    assert_eq!(requests.lock().unwrap().len(), 2);
+
+    // Rejections of the request itself are not retried.
+    let invalid = ImageTensor {
+        shape: vec![1, 6],
+        data: tensor.data.clone(),
+    };
+    assert!(client(3).infer(&invalid).await.is_err());
+    assert_eq!(requests.lock().unwrap().len(), 3);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn new_channels_wait_for_server_readiness() {
+    let addr: SocketAddr = "127.0.0.1:50078".parse().unwrap();
+    let shape = vec![1, 3, 2, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    )
+    .with_not_ready(2);
+    let ready_calls = Arc::clone(&mock_service.ready_calls);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = |checks| {
+        TritonClient::new(
+            format!("http://{}", addr),
+            "test-model",
+            "input",
+            "embedding",
+            false,
+            None,
+        )
+        .with_readiness_wait(checks, Duration::from_millis(10))
+    };
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+    };
+
+    let err = client(1).infer(&tensor).await.unwrap_err();
+    assert!(err.to_string().contains("not ready"), "{err}");
+    assert_eq!(ready_calls.load(Ordering::SeqCst), 1);
+
+    // One more not-ready answer, then ready; the probe is not repeated for
+    // later calls on the same channel.
+    let client = client(5);
+    assert_eq!(client.infer(&tensor).await.unwrap(), vec![0.25, 0.75]);
+    assert_eq!(ready_calls.load(Ordering::SeqCst), 3);
+    client.infer(&tensor).await.unwrap();
+    assert_eq!(ready_calls.load(Ordering::SeqCst), 3);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn model_version_is_sent_with_each_request() {
+    let addr: SocketAddr = "127.0.0.1:50079".parse().unwrap();
+    let shape = vec![1, 3, 2, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    );
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .with_model_version("2");
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+    };
+
+    client.infer(&tensor).await.unwrap();
+    client
+        .infer_with_version(&tensor, "req-1", Some("3"))
+        .await
+        .unwrap();
+
+    let versions: Vec<String> = requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|request| request.model_version.clone())
+        .collect();
+    assert_eq!(versions, vec!["2", "3"]);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn request_timeout_bounds_an_unresponsive_server() {
+    // Accepts connections but never speaks HTTP/2 back.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:50083")
+        .await
+        .unwrap();
+    let silent = tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            held.push(socket);
+        }
+    });
+
+    let client = TritonClient::new(
+        "http://127.0.0.1:50083",
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .with_timeouts(Duration::from_millis(200), Duration::from_millis(200));
+    let tensor = ImageTensor {
+        shape: vec![1, 3, 2, 1],
+        data: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+    };
+
+    let started = std::time::Instant::now();
+    let result = time::timeout(Duration::from_secs(5), client.infer(&tensor))
+        .await
+        .expect("timeouts were not applied");
+    assert!(
+        matches!(result, Err(TritonError::Transport(_))),
+        "{result:?}"
+    );
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    silent.abort();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn stream_yields_scores_in_order_and_isolates_failures() {
+    let addr: SocketAddr = "127.0.0.1:50084".parse().unwrap();
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        vec![1, 2],
+    );
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    );
+    let frame = |data: Vec<f32>| ImageTensor {
+        shape: vec![1, data.len() as i64],
+        data,
+    };
+    let frames = tokio_stream::iter(vec![
+        frame(vec![0.1, 0.2]),
+        frame(Vec::new()),
+        frame(vec![0.3, 0.4]),
+    ]);
+
+    let results: Vec<_> = client.infer_stream(frames).await.unwrap().collect().await;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap(), &vec![0.1, 0.2]);
+    match &results[1] {
+        Err(TritonError::InvalidResponse(message)) => assert_eq!(message, "missing fp32 contents"),
+        other => panic!("expected a per-message error, got {other:?}"),
+    }
+    assert_eq!(results[2].as_ref().unwrap(), &vec![0.3, 0.4]);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn raw_mode_sends_little_endian_bytes_without_contents() {
+    let addr: SocketAddr = "127.0.0.1:50085".parse().unwrap();
+    let shape = vec![1, 3, 1, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    );
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .with_raw_input(true);
+    let tensor = ImageTensor {
+        shape,
+        data: vec![1.0, -2.5, 0.0],
+    };
+    assert_eq!(client.infer(&tensor).await.unwrap(), vec![0.25, 0.75]);
+
+    let requests = requests.lock().unwrap();
+    let request = &requests[0];
+    assert_eq!(request.inputs.len(), request.raw_input_contents.len());
+    assert_eq!(request.inputs[0].datatype, "FP32");
+    assert!(request.inputs[0].contents.is_none());
+    assert_eq!(
+        request.raw_input_contents[0],
+        vec![0x00, 0x00, 0x80, 0x3F, 0x00, 0x00, 0x20, 0xC0, 0x00, 0x00, 0x00, 0x00]
+    );
+    drop(requests);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn binary_output_is_decoded_from_raw_contents() {
+    let addr: SocketAddr = "127.0.0.1:50102".parse().unwrap();
+    let shape = vec![1, 3, 1, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    )
+    .with_raw_output("quality", vec![0.875]);
+    let infer_requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .with_binary_output(true);
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3],
+    };
+
+    assert_eq!(client.infer(&tensor).await.unwrap(), vec![0.25, 0.75]);
+    let outputs = client.infer_outputs(&tensor).await.unwrap();
+    assert_eq!(outputs["embedding"], vec![0.25, 0.75]);
+    assert_eq!(outputs["quality"], vec![0.875]);
+
+    let requests = infer_requests.lock().unwrap();
+    let binary_data = requests[0].outputs[0].parameters["binary_data"].clone();
+    assert_eq!(
+        binary_data.parameter_choice,
+        Some(ParameterChoice::BoolParam(true))
+    );
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn infer_outputs_parses_every_fp32_output() {
+    let addr: SocketAddr = "127.0.0.1:50086".parse().unwrap();
+    let shape = vec![1, 3, 1, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    )
+    .with_raw_output("quality", vec![0.875]);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    );
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3],
+    };
+
+    let outputs = client.infer_outputs(&tensor).await.unwrap();
+    assert_eq!(outputs.len(), 2);
+    assert_eq!(outputs["embedding"], vec![0.25, 0.75]);
+    assert_eq!(outputs["quality"], vec![0.875]);
+    assert_eq!(client.infer(&tensor).await.unwrap(), vec![0.25, 0.75]);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn infer_outputs_parses_int32_outputs() {
+    let addr: SocketAddr = "127.0.0.1:50097".parse().unwrap();
+    let shape = vec![1, 3, 1, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    )
+    .with_raw_output("quality", vec![0.875])
+    .with_int_output("class_index", vec![7]);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    );
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3],
+    };
+
+    let outputs = client.infer_outputs(&tensor).await.unwrap();
+    assert_eq!(outputs.len(), 3);
+    assert_eq!(outputs["class_index"], vec![7.0]);
+    assert_eq!(outputs["quality"], vec![0.875]);
+
+    let class_client = client.for_model("test-model", "input", "class_index");
+    assert_eq!(class_client.infer(&tensor).await.unwrap(), vec![7.0]);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn request_id_reaches_outgoing_infer_request() {
+    let addr: SocketAddr = "127.0.0.1:50087".parse().unwrap();
+    let shape = vec![1, 3, 1, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    );
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    );
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3],
+    };
+    client.infer_with_id(&tensor, "req-42").await.unwrap();
+    client.infer(&tensor).await.unwrap();
+
+    let ids: Vec<String> = requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|request| request.id.clone())
+        .collect();
+    assert_eq!(ids, vec!["req-42".to_string(), String::new()]);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn validate_checks_names_and_shape_against_metadata() {
+    let addr: SocketAddr = "127.0.0.1:50088".parse().unwrap();
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        vec![-1, 3, 224, 224],
+    );
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = |input: &str, output: &str| {
+        TritonClient::new(
+            format!("http://{}", addr),
+            "test-model",
+            input,
+            output,
+            false,
+            None,
+        )
+    };
+
+    let matching = client("input", "embedding");
+    matching.validate(&[1, 3, 224, 224]).await.unwrap();
+    assert!(matches!(
+        matching.validate(&[1, 224, 224, 3]).await,
+        Err(TritonError::Configuration(_))
+    ));
+    assert!(matches!(
+        client("pixels", "embedding")
+            .validate(&[1, 3, 224, 224])
+            .await,
+        Err(TritonError::Configuration(_))
+    ));
+    assert!(matches!(
+        client("input", "logits").validate(&[1, 3, 224, 224]).await,
+        Err(TritonError::Configuration(_))
+    ));
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn uint8_input_sends_raw_pixel_values() {
+    let addr: SocketAddr = "127.0.0.1:50089".parse().unwrap();
+    let pixel = [12, 200, 77];
+    let mut png = Vec::new();
+    DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, Rgb(pixel)))
+        .write_to(&mut std::io::Cursor::new(&mut png), ImageOutputFormat::Png)
+        .unwrap();
+    let config = PreprocessConfig {
+        input_size: InputSize {
+            width: 2,
+            height: 2,
+        },
+        layout: TensorLayout::Nhwc,
+        ..Default::default()
+    };
+    let tensor = preprocess_with(&png, &config).unwrap();
+
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        tensor.shape.clone(),
+    );
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .with_input_dtype(InputDtype::Uint8);
+    client.infer(&tensor).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].inputs[0].datatype, "UINT8");
+    assert!(requests[0].inputs[0].contents.is_none());
+    assert_eq!(requests[0].raw_input_contents[0], pixel.repeat(4));
+    drop(requests);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
 
-use tonic::codegen::tokio_stream::Stream;
-use rust_service::{
-    triton_client::{
-        inference::{
-            self,
-            grpc_inference_service_server::{GrpcInferenceService, GrpcInferenceServiceServer},
-            model_infer_response, InferTensorContents, ModelInferRequest, ModelInferResponse,
-        },
-        TritonClient,
-    },
-    ImageTensor,
-};
-use tokio::{sync::oneshot, time};
-use tonic::{async_trait, transport::Server, Request, Response, Status};
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn liveness_check_scores_its_own_model_only() {
+    let addr: SocketAddr = "127.0.0.1:50090".parse().unwrap();
+    let shape = vec![1, 3, 1, 1];
+    let mock_service = MockTriton::new(
+        "face_verification".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    )
+    .with_model_scores("anti_spoof", vec![0.25]);
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let triton = TritonClient::new(
+        format!("http://{}", addr),
+        "face_verification",
+        "input",
+        "embedding",
+        false,
+        None,
+    );
+    let model = triton.for_model("anti_spoof", "input", "liveness");
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3],
+    };
+
+    let strict = LivenessCheck::new(model.clone(), VerifyThreshold::new(0.8).unwrap());
+    assert_eq!(
+        strict.check(&tensor, "req-1").await.unwrap(),
+        Liveness {
+            score: 0.25,
+            live: false
+        }
+    );
+    let lenient = LivenessCheck::new(model, VerifyThreshold::new(0.2).unwrap());
+    assert!(lenient.check(&tensor, "req-2").await.unwrap().live);
+
+    let requests = requests.lock().unwrap();
+    assert!(
+        requests
+            .iter()
+            .all(|request| request.model_name == "anti_spoof"
+                && request.outputs[0].name == "liveness")
+    );
+    assert_eq!(requests.len(), 2);
+    drop(requests);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
-async fn infer_request_serializes_expected_tensor() {
-    let addr: SocketAddr = "127.0.0.1:50070".parse().unwrap();
-    let model_name = "test-model".to_string();
-    let input_name = "input".to_string();
-    let output_name = "embedding".to_string();
-    let expected_shape = vec![1, 3, 2, 1];
+async fn allowed_model_selection_routes_to_that_model() {
+    let addr: SocketAddr = "127.0.0.1:50091".parse().unwrap();
+    let shape = vec![1, 3, 1, 1];
+    let mock_service = MockTriton::new(
+        "face_verification".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    )
+    .with_model_scores("face_verification_eu", vec![0.5, 0.125]);
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let triton = TritonClient::new(
+        format!("http://{}", addr),
+        "face_verification",
+        "input",
+        "embedding",
+        false,
+        None,
+    );
+    let allowlist: ModelAllowlist = "face_verification_eu, face_verification_hq"
+        .parse()
+        .unwrap();
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3],
+    };
+
+    allowlist.check("face_verification_eu").unwrap();
+    let scores = triton
+        .infer_with_model("face_verification_eu", &tensor, "req-1")
+        .await
+        .unwrap();
+    assert_eq!(scores, vec![0.5, 0.125]);
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].model_name, "face_verification_eu");
+    assert_eq!(requests[0].inputs[0].name, "pixels");
+    drop(requests);
+
+    let mut violations = FieldViolations::default();
+    if let Err(message) = allowlist.check("../secret_model") {
+        violations.add("model", message);
+    }
+    let status = violations.into_result().unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(status.message(), "model '../secret_model' is not allowed");
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn model_specs_select_tensor_names_per_model() {
+    let addr: SocketAddr = "127.0.0.1:50098".parse().unwrap();
+    let shape = vec![1, 3, 1, 1];
     let mock_service = MockTriton::new(
-        model_name.clone(),
-        input_name.clone(),
-        output_name.clone(),
-        expected_shape.clone(),
+        "face_verification".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    )
+    .with_model_scores("face_a", vec![0.5])
+    .with_model_scores("face_b", vec![0.75]);
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let metadata_calls = Arc::clone(&mock_service.metadata_calls);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let specs = ModelSpecs::from_json(
+        r#"{
+            "face_a": {"input_name": "pixels_a", "output_name": "score_a"},
+            "face_b": {
+                "input_name": "pixels_b",
+                "output_name": "score_b",
+                "datatype": "FP16",
+                "shape": [3, -1, 1]
+            }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(specs.len(), 2);
+    let triton = TritonClient::new(
+        format!("http://{}", addr),
+        "face_verification",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .with_model_specs(specs);
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3],
+    };
+
+    let a = triton.infer_with_model("face_a", &tensor, "req-1").await;
+    assert_eq!(a.unwrap(), vec![0.5]);
+    let b = triton.infer_with_model("face_b", &tensor, "req-2").await;
+    assert_eq!(b.unwrap(), vec![0.75]);
+
+    let recorded = requests.lock().unwrap();
+    assert_eq!(recorded.len(), 2);
+    assert_eq!(recorded[0].model_name, "face_a");
+    assert_eq!(recorded[0].inputs[0].name, "pixels_a");
+    assert_eq!(recorded[0].inputs[0].datatype, "FP32");
+    assert_eq!(recorded[0].outputs[0].name, "score_a");
+    assert_eq!(recorded[1].model_name, "face_b");
+    assert_eq!(recorded[1].inputs[0].name, "pixels_b");
+    assert_eq!(recorded[1].inputs[0].datatype, "FP16");
+    assert_eq!(recorded[1].outputs[0].name, "score_b");
+    drop(recorded);
+    // Configured models skip metadata discovery.
+    assert_eq!(metadata_calls.load(Ordering::SeqCst), 0);
+
+    let wrong_shape = ImageTensor {
+        shape: vec![1, 1, 1, 3],
+        data: vec![0.1, 0.2, 0.3],
+    };
+    let err = triton
+        .infer_with_model("face_b", &wrong_shape, "req-3")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, TritonError::Configuration(_)), "{err}");
+    assert_eq!(requests.lock().unwrap().len(), 2);
+
+    assert!(matches!(
+        ModelSpecs::from_json(r#"{"face_c": {"input_name": "", "output_name": "score"}}"#),
+        Err(ModelSpecError::InvalidModel { .. })
+    ));
+    assert!(matches!(
+        ModelSpecs::from_json(
+            r#"{"face_c": {"input_name": "x", "output_name": "y", "datatype": "INT8"}}"#
+        ),
+        Err(ModelSpecError::Parse(_))
+    ));
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn face_detector_crops_to_the_most_confident_box() {
+    let addr: SocketAddr = "127.0.0.1:50099".parse().unwrap();
+    let mock_service = MockTriton::new(
+        "face_verification".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        vec![1, 3, 1, 1],
+    )
+    // Rows of [x1, y1, x2, y2, confidence].
+    .with_model_scores(
+        "face_detector",
+        vec![
+            0.0, 0.0, 0.25, 0.25, 0.6, //
+            0.125, 0.25, 0.625, 0.75, 0.9, //
+            0.5, 0.5, 1.0, 1.0, 0.3,
+        ],
+    )
+    .with_model_scores("empty_detector", vec![0.0, 0.0, 1.0, 1.0, 0.2]);
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let triton = TritonClient::new(
+        format!("http://{}", addr),
+        "face_verification",
+        "input",
+        "embedding",
+        false,
+        None,
     );
+    let size = InputSize {
+        width: 32,
+        height: 16,
+    };
+    let detector = FaceDetector::new(triton.for_model("face_detector", "input", "boxes"), size)
+        .with_min_confidence(0.5)
+        .with_margin(0.25);
+    let mut photo = RgbImage::from_pixel(256, 128, Rgb([0, 0, 0]));
+    photo.put_pixel(10, 20, Rgb([255, 0, 0]));
+    let photo = DynamicImage::ImageRgb8(photo);
 
-    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    let face = detector.detect(&photo, "req-1").await.unwrap().unwrap();
+    assert_eq!(
+        face,
+        FaceBox {
+            x1: 0.125,
+            y1: 0.25,
+            x2: 0.625,
+            y2: 0.75,
+            confidence: 0.9
+        }
+    );
+    // A quarter of the box size is added on every side, clipped at the left.
+    assert_eq!(face.crop_rect(256, 128, 0.25), Some((0, 16, 192, 96)));
 
-    let server = tokio::spawn(async move {
-        Server::builder()
-            .add_service(GrpcInferenceServiceServer::new(mock_service))
-            .serve_with_shutdown(addr, async {
-                let _ = shutdown_rx.await;
-            })
-            .await
-            .unwrap();
-    });
+    let cropped = detector.crop(&photo, "req-2").await.unwrap();
+    assert_eq!((cropped.width(), cropped.height()), (192, 96));
+    assert_eq!(cropped.to_rgb8().get_pixel(10, 4), &Rgb([255, 0, 0]));
 
-    time::sleep(Duration::from_millis(50)).await;
+    let recorded = requests.lock().unwrap();
+    assert_eq!(recorded[0].model_name, "face_detector");
+    assert_eq!(recorded[0].inputs[0].shape, vec![1, 3, 16, 32]);
+    drop(recorded);
+
+    let empty = FaceDetector::new(triton.for_model("empty_detector", "input", "boxes"), size);
+    assert!(matches!(
+        empty.crop(&photo, "req-3").await,
+        Err(FaceError::NotDetected)
+    ));
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn warmup_sends_one_zero_tensor() {
+    let addr: SocketAddr = "127.0.0.1:50092".parse().unwrap();
+    let shape = vec![1, 3, 2, 2];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    );
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
 
     let client = TritonClient::new(
         format!("http://{}", addr),
-        model_name,
-        input_name,
-        output_name,
+        "test-model",
+        "input",
+        "embedding",
         false,
         None,
     );
+    client.warmup(&shape).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].id, "warmup");
+    let contents = requests[0].inputs[0].contents.as_ref().unwrap();
+    assert_eq!(contents.fp32_contents, vec![0.0; 12]);
+    drop(requests);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn compressed_channel_round_trips() {
+    let addr: SocketAddr = "127.0.0.1:50093".parse().unwrap();
+    let shape = vec![1, 3, 2, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    );
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
 
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .with_compression(true)
+    .unwrap();
     let tensor = ImageTensor {
-        shape: expected_shape,
+        shape,
         data: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
     };
 
-    let scores = client.infer(&tensor).await.unwrap();
-    assert_eq!(scores, vec![0.25, 0.75]);
+    assert_eq!(client.infer(&tensor).await.unwrap(), vec![0.25, 0.75]);
+    let requests = requests.lock().unwrap();
+    let contents = requests[0].inputs[0].contents.as_ref().unwrap();
+    assert_eq!(contents.fp32_contents, tensor.data);
+    drop(requests);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[cfg(not(feature = "gzip"))]
+#[test]
+fn compression_without_gzip_feature_is_a_configuration_error() {
+    let result = TritonClient::new("http://triton:8001", "m", "input", "output", false, None)
+        .with_compression(true);
+    assert!(matches!(result, Err(TritonError::Configuration(_))));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn requests_spread_across_local_endpoints() {
+    let shape = vec![1, 3, 1, 1];
+    let mut mocks = Vec::new();
+    let mut endpoints = Vec::new();
+    for port in [50094, 50095] {
+        let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+        let mock_service = MockTriton::new(
+            "test-model".to_string(),
+            "input".to_string(),
+            "embedding".to_string(),
+            shape.clone(),
+        );
+        let requests = Arc::clone(&mock_service.infer_requests);
+        mocks.push((requests, spawn_mock(addr, mock_service).await));
+        endpoints.push(format!("http://{}", addr));
+    }
+
+    let client = TritonClient::new(
+        endpoints[0].clone(),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .with_local_endpoints([endpoints[1].clone()]);
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3],
+    };
+    for _ in 0..50 {
+        client.infer(&tensor).await.unwrap();
+    }
+
+    for (requests, (shutdown_tx, server)) in mocks {
+        assert!(!requests.lock().unwrap().is_empty());
+        shutdown_tx.send(()).unwrap();
+        server.await.unwrap();
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_infers_share_a_bounded_channel_pool() {
+    let addr: SocketAddr = "127.0.0.1:50100".parse().unwrap();
+    let shape = vec![1, 3, 1, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    );
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .with_pool_size(3);
+    assert_eq!(client.pool_size(), 3);
+    // Channels are only opened once they are needed.
+    assert_eq!(client.open_connections().await, 0);
+
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3],
+    };
+    let results = futures_util::future::join_all((0..64).map(|_| client.infer(&tensor))).await;
+    for scores in results {
+        assert_eq!(scores.unwrap(), vec![0.25, 0.75]);
+    }
+    assert_eq!(requests.lock().unwrap().len(), 64);
+    assert_eq!(client.open_connections().await, 3);
+
+    let single = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    )
+    .with_pool_size(0);
+    assert_eq!(single.pool_size(), 1);
+    single.infer(&tensor).await.unwrap();
+    single.infer(&tensor).await.unwrap();
+    assert_eq!(single.open_connections().await, 1);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn caller_deadline_cuts_off_slow_inference() {
+    let addr: SocketAddr = "127.0.0.1:50101".parse().unwrap();
+    let shape = vec![1, 3, 1, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    )
+    .with_delay(Duration::from_millis(500));
+    let grpc_timeouts = Arc::clone(&mock_service.grpc_timeouts);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    );
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3],
+    };
+
+    let started = Instant::now();
+    let err = client
+        .clone()
+        .with_deadline(started + Duration::from_millis(100))
+        .infer(&tensor)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, TritonError::DeadlineExceeded), "{err}");
+    assert_eq!(err.error_code().status_code(), Code::DeadlineExceeded);
+    assert!(started.elapsed() < Duration::from_millis(400));
+    // The remaining budget travels to Triton as grpc-timeout.
+    let timeouts = grpc_timeouts.lock().unwrap().clone();
+    assert_eq!(timeouts.len(), 1);
+    let sent = deadline::parse_grpc_timeout(&timeouts[0]).unwrap();
+    assert!(sent <= Duration::from_millis(100) && sent > Duration::ZERO);
+
+    // An already expired deadline never reaches Triton.
+    let expired = client.clone().with_deadline(Instant::now());
+    assert!(matches!(
+        expired.infer(&tensor).await,
+        Err(TritonError::DeadlineExceeded)
+    ));
+    assert_eq!(grpc_timeouts.lock().unwrap().len(), 1);
+
+    // Without a deadline the configured request timeout still applies.
+    assert_eq!(client.infer(&tensor).await.unwrap(), vec![0.25, 0.75]);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn score_cache_skips_repeat_inference() {
+    let addr: SocketAddr = "127.0.0.1:50096".parse().unwrap();
+    let shape = vec![1, 3, 1, 1];
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        shape.clone(),
+    );
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let client = TritonClient::new(
+        format!("http://{}", addr),
+        "test-model",
+        "input",
+        "embedding",
+        false,
+        None,
+    );
+    let tensor = ImageTensor {
+        shape,
+        data: vec![0.1, 0.2, 0.3],
+    };
+    let cache = ScoreCache::new(8, Duration::from_secs(60));
+    let key = |variant: &str| ScoreCache::key(b"same image bytes", variant);
+
+    for _ in 0..2 {
+        let scores = cached_scores(&cache, key("default"), &client, &tensor).await;
+        assert_eq!(scores, vec![0.25, 0.75]);
+    }
+    assert_eq!(requests.lock().unwrap().len(), 1);
+
+    cached_scores(&cache, key("rotated"), &client, &tensor).await;
+    assert_eq!(requests.lock().unwrap().len(), 2);
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+// The same get-then-insert the service does around inference.
+async fn cached_scores(
+    cache: &ScoreCache,
+    key: CacheKey,
+    client: &TritonClient,
+    tensor: &ImageTensor,
+) -> Vec<f32> {
+    if let Some(scores) = cache.get(&key) {
+        return scores.to_vec();
+    }
+    let scores = client.infer(tensor).await.unwrap();
+    cache.insert(key, scores.clone());
+    scores
+}
+
+// Kills the spawned service even when an assertion fails first.
+struct ServiceProcess(std::process::Child);
+
+impl Drop for ServiceProcess {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn dry_run_service_never_calls_triton() {
+    let addr: SocketAddr = "127.0.0.1:50105".parse().unwrap();
+    let mock_service = MockTriton::new(
+        "test-model".to_string(),
+        "input".to_string(),
+        "embedding".to_string(),
+        vec![1, 3, 224, 224],
+    );
+    let requests = Arc::clone(&mock_service.infer_requests);
+    let metadata_calls = Arc::clone(&mock_service.metadata_calls);
+    let (shutdown_tx, server) = spawn_mock(addr, mock_service).await;
+
+    let _service = ServiceProcess(
+        std::process::Command::new(env!("CARGO_BIN_EXE_rust-service"))
+            .env("DRY_RUN", "true")
+            .env("TRITON_ENDPOINT", format!("http://{addr}"))
+            .env("TRITON_MODEL_NAME", "test-model")
+            .env("METRICS_ADDR", "127.0.0.1:0")
+            .spawn()
+            .unwrap(),
+    );
+    let mut client = None;
+    for _ in 0..100 {
+        if let Ok(connected) = ImageProcessorClient::connect("http://127.0.0.1:50051").await {
+            client = Some(connected);
+            break;
+        }
+        time::sleep(Duration::from_millis(100)).await;
+    }
+    let mut client = client.expect("service did not start listening");
+
+    let mut png = Vec::new();
+    DynamicImage::ImageRgb8(RgbImage::from_pixel(96, 96, Rgb([204, 51, 0])))
+        .write_to(&mut std::io::Cursor::new(&mut png), ImageOutputFormat::Png)
+        .unwrap();
+    let response = client
+        .process_image(VerifyRequest {
+            user_id: "user-1".to_string(),
+            image_data: png,
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(response.message.contains("dry run"), "{}", response.message);
+    assert_eq!(requests.lock().unwrap().len(), 0);
+    assert_eq!(metadata_calls.load(Ordering::SeqCst), 0);
 
     shutdown_tx.send(()).unwrap();
     server.await.unwrap();
 }
 
+#[test]
+fn fp16_encoding_rounds_to_nearest_half() {
+    assert_eq!(encode_raw_fp16(&[1.0 / 3.0]), vec![0x55, 0x35]);
+}
+
+async fn spawn_mock(
+    addr: SocketAddr,
+    mock_service: MockTriton,
+) -> (oneshot::Sender<()>, JoinHandle<()>) {
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+    let service = GrpcInferenceServiceServer::new(mock_service);
+    #[cfg(feature = "gzip")]
+    let service = service
+        .accept_compressed(CompressionEncoding::Gzip)
+        .send_compressed(CompressionEncoding::Gzip);
+    let server = tokio::spawn(async move {
+        Server::builder()
+            .add_service(service)
+            .serve_with_shutdown(addr, async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+            .unwrap();
+    });
+
+    time::sleep(Duration::from_millis(50)).await;
+
+    (shutdown_tx, server)
+}
+
 #[derive(Clone)]
 struct MockTriton {
     model_name: String,
     input_name: String,
     output_name: String,
     expected_shape: Vec<i64>,
+    output_shape: Vec<i64>,
+    metadata_calls: Arc<AtomicUsize>,
+    repository_calls: Arc<Mutex<Vec<String>>>,
+    infer_requests: Arc<Mutex<Vec<ModelInferRequest>>>,
+    failures_left: Arc<AtomicUsize>,
+    not_ready_left: Arc<AtomicUsize>,
+    ready_calls: Arc<AtomicUsize>,
+    raw_output: Option<(String, Vec<f32>)>,
+    int_output: Option<(String, Vec<i32>)>,
+    model_scores: HashMap<String, Vec<f32>>,
+    delay: Duration,
+    grpc_timeouts: Arc<Mutex<Vec<String>>>,
 }
 
 impl MockTriton {
@@ -85,8 +1548,62 @@ impl MockTriton {
             input_name,
             output_name,
             expected_shape,
+            output_shape: vec![2],
+            metadata_calls: Arc::default(),
+            repository_calls: Arc::default(),
+            infer_requests: Arc::default(),
+            failures_left: Arc::default(),
+            not_ready_left: Arc::default(),
+            ready_calls: Arc::default(),
+            raw_output: None,
+            int_output: None,
+            model_scores: HashMap::new(),
+            delay: Duration::ZERO,
+            grpc_timeouts: Arc::default(),
         }
     }
+
+    fn with_output_shape(mut self, shape: Vec<i64>) -> Self {
+        self.output_shape = shape;
+        self
+    }
+
+    // Adds a second output whose values travel in raw_output_contents.
+    fn with_raw_output(mut self, name: &str, values: Vec<f32>) -> Self {
+        self.raw_output = Some((name.to_string(), values));
+        self
+    }
+
+    // Adds an INT32 output, such as a class index, sent in int_contents.
+    fn with_int_output(mut self, name: &str, values: Vec<i32>) -> Self {
+        self.int_output = Some((name.to_string(), values));
+        self
+    }
+
+    // Requests for `model_name` are answered with `scores` on whichever
+    // output they ask for, without the input checks of the main model.
+    fn with_model_scores(mut self, model_name: &str, scores: Vec<f32>) -> Self {
+        self.model_scores.insert(model_name.to_string(), scores);
+        self
+    }
+
+    // Every inference call takes at least `delay` to answer.
+    fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    // The first `count` readiness checks report the server as still loading.
+    fn with_not_ready(self, count: usize) -> Self {
+        self.not_ready_left.store(count, Ordering::SeqCst);
+        self
+    }
+
+    // The first `count` inference calls report the server as unavailable.
+    fn with_failures(self, count: usize) -> Self {
+        self.failures_left.store(count, Ordering::SeqCst);
+        self
+    }
 }
 
 type MockStream =
@@ -107,7 +1624,16 @@ impl GrpcInferenceService for MockTriton {
         &self,
         _request: Request<inference::ServerReadyRequest>,
     ) -> Result<Response<inference::ServerReadyResponse>, Status> {
-        Err(Status::unimplemented("server_ready"))
+        self.ready_calls.fetch_add(1, Ordering::SeqCst);
+        let not_ready = self
+            .not_ready_left
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok();
+        Ok(Response::new(inference::ServerReadyResponse {
+            ready: !not_ready,
+        }))
     }
 
     async fn model_ready(
@@ -126,19 +1652,97 @@ impl GrpcInferenceService for MockTriton {
 
     async fn model_metadata(
         &self,
-        _request: Request<inference::ModelMetadataRequest>,
+        request: Request<inference::ModelMetadataRequest>,
     ) -> Result<Response<inference::ModelMetadataResponse>, Status> {
-        Err(Status::unimplemented("model_metadata"))
+        self.metadata_calls.fetch_add(1, Ordering::SeqCst);
+        let name = request.into_inner().name;
+        if self.model_scores.contains_key(&name) {
+            let tensor = |name: &str| inference::model_metadata_response::TensorMetadata {
+                name: name.to_string(),
+                datatype: "FP32".to_string(),
+                shape: Vec::new(),
+            };
+            return Ok(Response::new(inference::ModelMetadataResponse {
+                name,
+                versions: vec!["1".to_string()],
+                platform: "mock".to_string(),
+                inputs: vec![tensor("pixels")],
+                outputs: vec![tensor("scores")],
+            }));
+        }
+        if name != self.model_name {
+            return Err(Status::not_found("unknown model"));
+        }
+
+        let tensor =
+            |name: &str, shape: &[i64]| inference::model_metadata_response::TensorMetadata {
+                name: name.to_string(),
+                datatype: "FP32".to_string(),
+                shape: shape.to_vec(),
+            };
+        Ok(Response::new(inference::ModelMetadataResponse {
+            name: self.model_name.clone(),
+            versions: vec!["1".to_string()],
+            platform: "mock".to_string(),
+            inputs: vec![tensor(&self.input_name, &self.expected_shape)],
+            outputs: vec![tensor(&self.output_name, &self.output_shape)],
+        }))
     }
 
     async fn model_infer(
         &self,
         request: Request<ModelInferRequest>,
     ) -> Result<Response<ModelInferResponse>, Status> {
+        if let Some(timeout) = request.metadata().get("grpc-timeout") {
+            let timeout = timeout.to_str().unwrap_or_default().to_string();
+            self.grpc_timeouts.lock().unwrap().push(timeout);
+        }
         let request = request.into_inner();
+        self.infer_requests.lock().unwrap().push(request.clone());
+        time::sleep(self.delay).await;
+        let failing = self
+            .failures_left
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok();
+        if failing {
+            return Err(Status::unavailable("model is restarting"));
+        }
+        if let Some(scores) = self.model_scores.get(&request.model_name) {
+            let output_name = request
+                .outputs
+                .first()
+                .map(|output| output.name.clone())
+                .unwrap_or_default();
+            return Ok(Response::new(ModelInferResponse {
+                model_name: request.model_name,
+                outputs: vec![model_infer_response::InferOutputTensor {
+                    name: output_name,
+                    datatype: "FP32".to_string(),
+                    shape: vec![scores.len() as i64],
+                    parameters: HashMap::new(),
+                    contents: Some(InferTensorContents {
+                        fp32_contents: scores.clone(),
+                        ..Default::default()
+                    }),
+                }],
+                ..Default::default()
+            }));
+        }
         if request.model_name != self.model_name {
             return Err(Status::invalid_argument("unexpected model name"));
         }
+        let binary_output = request.outputs.iter().any(|output| {
+            output.name == self.output_name
+                && matches!(
+                    output.parameters.get("binary_data"),
+                    Some(InferParameter {
+                        parameter_choice: Some(ParameterChoice::BoolParam(true)),
+                    })
+                )
+        });
+        let raw_input = !request.raw_input_contents.is_empty();
         let input = request
             .inputs
             .into_iter()
@@ -150,28 +1754,65 @@ impl GrpcInferenceService for MockTriton {
         if input.shape != self.expected_shape {
             return Err(Status::invalid_argument("unexpected input shape"));
         }
-        let contents = input
-            .contents
-            .ok_or_else(|| Status::invalid_argument("missing input contents"))?;
-        if contents.fp32_contents.is_empty() {
-            return Err(Status::invalid_argument("missing fp32 contents"));
+        if !raw_input {
+            let contents = input
+                .contents
+                .ok_or_else(|| Status::invalid_argument("missing input contents"))?;
+            if contents.fp32_contents.is_empty() {
+                return Err(Status::invalid_argument("missing fp32 contents"));
+            }
         }
 
+        let scores = vec![0.25, 0.75];
+        let mut raw_output_contents = Vec::new();
+        if binary_output {
+            raw_output_contents.push(encode_raw_fp32(&scores));
+        }
         let response_tensor = model_infer_response::InferOutputTensor {
             name: self.output_name.clone(),
             datatype: "FP32".to_string(),
-            shape: vec![2],
+            shape: self.output_shape.clone(),
             parameters: HashMap::new(),
-            contents: Some(InferTensorContents {
-                fp32_contents: vec![0.25, 0.75],
+            contents: (!binary_output).then(|| InferTensorContents {
+                fp32_contents: scores,
                 ..Default::default()
             }),
         };
 
+        let mut outputs = vec![response_tensor];
+        if let Some((name, values)) = &self.raw_output {
+            outputs.push(model_infer_response::InferOutputTensor {
+                name: name.clone(),
+                datatype: "FP32".to_string(),
+                shape: vec![values.len() as i64],
+                parameters: HashMap::new(),
+                contents: None,
+            });
+            if raw_output_contents.is_empty() {
+                raw_output_contents.push(Vec::new());
+            }
+            raw_output_contents.push(encode_raw_fp32(values));
+        }
+        if let Some((name, values)) = &self.int_output {
+            outputs.push(model_infer_response::InferOutputTensor {
+                name: name.clone(),
+                datatype: "INT32".to_string(),
+                shape: vec![values.len() as i64],
+                parameters: HashMap::new(),
+                contents: Some(InferTensorContents {
+                    int_contents: values.clone(),
+                    ..Default::default()
+                }),
+            });
+            if !raw_output_contents.is_empty() {
+                raw_output_contents.push(Vec::new());
+            }
+        }
+
         let response = ModelInferResponse {
             model_name: self.model_name.clone(),
-            outputs: vec![response_tensor],
-            raw_output_contents: Vec::new(),
+            outputs,
+            raw_output_contents,
             ..Default::default()
         };
 
@@ -180,9 +1821,45 @@ impl GrpcInferenceService for MockTriton {
 
     async fn model_stream_infer(
         &self,
-        _request: Request<tonic::Streaming<ModelInferRequest>>,
+        request: Request<tonic::Streaming<ModelInferRequest>>,
     ) -> Result<Response<Self::ModelStreamInferStream>, Status> {
-        Err(Status::unimplemented("model_stream_infer"))
+        // Echoes each request's input values back as its scores.
+        let model_name = self.model_name.clone();
+        let output_name = self.output_name.clone();
+        let responses = request.into_inner().map(move |request| {
+            let values = request?
+                .inputs
+                .into_iter()
+                .next()
+                .and_then(|input| input.contents)
+                .map(|contents| contents.fp32_contents)
+                .unwrap_or_default();
+            if values.is_empty() {
+                return Ok(inference::ModelStreamInferResponse {
+                    error_message: "missing fp32 contents".to_string(),
+                    infer_response: None,
+                });
+            }
+            let output = model_infer_response::InferOutputTensor {
+                name: output_name.clone(),
+                datatype: "FP32".to_string(),
+                shape: vec![values.len() as i64],
+                parameters: HashMap::new(),
+                contents: Some(InferTensorContents {
+                    fp32_contents: values,
+                    ..Default::default()
+                }),
+            };
+            Ok(inference::ModelStreamInferResponse {
+                error_message: String::new(),
+                infer_response: Some(ModelInferResponse {
+                    model_name: model_name.clone(),
+                    outputs: vec![output],
+                    ..Default::default()
+                }),
+            })
+        });
+        Ok(Response::new(Box::pin(responses)))
     }
 
     async fn model_config(
@@ -208,16 +1885,26 @@ impl GrpcInferenceService for MockTriton {
 
     async fn repository_model_load(
         &self,
-        _request: Request<inference::RepositoryModelLoadRequest>,
+        request: Request<inference::RepositoryModelLoadRequest>,
     ) -> Result<Response<inference::RepositoryModelLoadResponse>, Status> {
-        Err(Status::unimplemented("repository_model_load"))
+        let name = request.into_inner().model_name;
+        self.repository_calls
+            .lock()
+            .unwrap()
+            .push(format!("load:{name}"));
+        Ok(Response::new(inference::RepositoryModelLoadResponse {}))
     }
 
     async fn repository_model_unload(
         &self,
-        _request: Request<inference::RepositoryModelUnloadRequest>,
+        request: Request<inference::RepositoryModelUnloadRequest>,
     ) -> Result<Response<inference::RepositoryModelUnloadResponse>, Status> {
-        Err(Status::unimplemented("repository_model_unload"))
+        let name = request.into_inner().model_name;
+        self.repository_calls
+            .lock()
+            .unwrap()
+            .push(format!("unload:{name}"));
+        Ok(Response::new(inference::RepositoryModelUnloadResponse {}))
     }
 
     async fn system_shared_memory_status(
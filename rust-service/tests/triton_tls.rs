@@ -0,0 +1,51 @@
+use rust_service::triton_client::{TritonClient, TritonError};
+
+fn tls_client() -> TritonClient {
+    TritonClient::new(
+        "https://triton.internal:8001",
+        "model",
+        "input",
+        "output",
+        true,
+        Some("/etc/triton/ca.pem".to_string()),
+    )
+}
+
+#[test]
+fn client_identity_is_configured_when_both_paths_are_given() {
+    let client = tls_client()
+        .with_client_identity(
+            Some("/etc/triton/client.pem".to_string()),
+            Some("/etc/triton/client.key".to_string()),
+        )
+        .unwrap();
+    assert!(client.has_client_identity());
+
+    let client = tls_client().with_client_identity(None, None).unwrap();
+    assert!(!client.has_client_identity());
+}
+
+#[test]
+fn client_identity_requires_both_paths_and_tls() {
+    for (certificate, key) in [
+        (Some("/etc/triton/client.pem".to_string()), None),
+        (None, Some("/etc/triton/client.key".to_string())),
+    ] {
+        let result = tls_client().with_client_identity(certificate, key);
+        assert!(matches!(result, Err(TritonError::Configuration(_))));
+    }
+
+    let plaintext = TritonClient::new(
+        "http://triton:8001",
+        "model",
+        "input",
+        "output",
+        false,
+        None,
+    );
+    let result = plaintext.with_client_identity(
+        Some("/etc/triton/client.pem".to_string()),
+        Some("/etc/triton/client.key".to_string()),
+    );
+    assert!(matches!(result, Err(TritonError::Configuration(_))));
+}
@@ -0,0 +1,30 @@
+use rust_service::usage::{tenant, thread_cpu_time, TENANT_HEADER, UNKNOWN_TENANT};
+use tonic::Request;
+
+#[test]
+fn tenant_is_read_from_metadata() {
+    let mut request = Request::new(());
+    request
+        .metadata_mut()
+        .insert(TENANT_HEADER, "acme".parse().unwrap());
+
+    assert_eq!(tenant(&request), "acme");
+}
+
+#[test]
+fn missing_tenant_is_attributed_to_unknown() {
+    assert_eq!(tenant(&Request::new(())), UNKNOWN_TENANT);
+}
+
+#[cfg(unix)]
+#[test]
+fn thread_cpu_time_advances_with_work() {
+    let start = thread_cpu_time().unwrap();
+    let mut acc = 0u64;
+    for i in 0..5_000_000u64 {
+        acc = std::hint::black_box(acc.wrapping_mul(31).wrapping_add(i));
+    }
+    let end = thread_cpu_time().unwrap();
+
+    assert!(end > start, "{acc}");
+}